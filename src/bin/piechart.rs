@@ -41,12 +41,12 @@ fn main() {
         .unwrap()
         .parse()
         .unwrap_or_else(|err| parse_value_error("radius", err));
-    let aspect_ratio = matches
+    let aspect_ratio: f32 = matches
         .value_of("aspect ratio")
         .unwrap()
         .parse()
         .unwrap_or_else(|err| parse_value_error("aspect ratio", err));
-    if aspect_ratio == 0 {
+    if !(aspect_ratio > 0.0 && aspect_ratio.is_finite()) {
         clap_err("the aspect ratio should be greater than 0");
     }
     let legend = !matches.is_present("no legend");
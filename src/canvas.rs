@@ -0,0 +1,107 @@
+use crate::Style;
+use std::fmt;
+use unicode_width::UnicodeWidthChar;
+
+/// A single character on the canvas.
+#[derive(Debug, Clone, Copy)]
+pub struct Cell {
+    pub symbol: char,
+    pub style: Option<Style>,
+}
+
+impl Cell {
+    pub fn new(symbol: char, style: Option<Style>) -> Self {
+        Cell { symbol, style }
+    }
+
+    /// The amount of columns the symbol takes up in the terminal.
+    pub fn width(&self) -> usize {
+        self.symbol.width().unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            None => write!(f, "{}", self.symbol),
+            Some(style) => write!(f, "{}", style.paint(self.symbol.to_string())),
+        }
+    }
+}
+
+/// A grid of cells which the chart is rendered into before writing it out.
+#[derive(Debug)]
+pub struct Canvas {
+    width: usize,
+    cells: Vec<Option<Cell>>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            width,
+            cells: vec![None; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len() / self.width.max(1)
+    }
+
+    /// Sets the cell at (`x`, `y`). A wide char also clears the cell after it, which it covers.
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        self.cells[y * self.width + x] = Some(cell);
+        if cell.width() > 1 && x + 1 < self.width {
+            self.cells[y * self.width + x + 1] = None;
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        self.cells[y * self.width + x]
+    }
+
+    /// Writes `text` starting at (`x`, `y`), wide chars take up two cells.
+    pub fn text(&mut self, x: usize, y: usize, text: &str, style: Option<Style>) {
+        let mut x = x;
+        for symbol in text.chars() {
+            let cell = Cell::new(symbol, style);
+            self.set(x, y, cell);
+            x += cell.width();
+        }
+    }
+
+    /// Renders the row `y`. Unless `trim` is false, empty cells at the end are omitted.
+    /// The cell after a wide char is skipped, since the char covers it. If that cell was drawn over
+    /// or is outside of the canvas, the wide char is replaced by a space instead.
+    pub fn line(&self, y: usize, trim: bool) -> String {
+        let row = &self.cells[y * self.width..(y + 1) * self.width];
+        let len = match trim {
+            true => row.iter().rposition(Option::is_some).map_or(0, |i| i + 1),
+            false => row.len(),
+        };
+
+        let mut covered = false;
+        let mut line = String::new();
+        for (x, cell) in row[..len].iter().enumerate() {
+            let free = match row.get(x + 1) {
+                Some(None) => true,
+                Some(Some(next)) => next.symbol == ' ',
+                None => false,
+            };
+            match (covered, cell) {
+                (true, _) => covered = false,
+                (false, Some(cell)) if cell.width() > 1 && !free => line.push(' '),
+                (false, Some(cell)) => {
+                    covered = cell.width() > 1;
+                    line.push_str(&cell.to_string());
+                }
+                (false, None) => line.push(' '),
+            }
+        }
+        line
+    }
+}
@@ -1,8 +1,97 @@
-use crate::{utils, Data};
-use std::io;
+use crate::canvas::{Canvas, Cell};
+use crate::geometry::{Coverage, Geometry};
+use crate::legend::{LegendEntry, LegendFormat};
+use crate::number::{NumberFormat, Percentages, UnitPrefix};
+use crate::{terminal, utils, Data, Style};
+use std::{io, iter};
 
 const LABEL_PADDING: usize = 2;
 
+/// The way the area of the slices is drawn, see [`Chart::render_mode`](struct.Chart.html#method.render_mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Each cell is drawn with the [`fill`](struct.Data.html#structfield.fill) char of its slice.
+    Fill,
+    /// Each cell is drawn as a braille pattern with 2x4 dots, which gives small charts a much higher resolution.
+    /// Since a cell can only have one color, only the dots of the slice covering most of the cell are set.
+    Braille,
+    /// Each cell is drawn as `▀` or `▄` with the color of the upper slice as foreground and the color of the lower one
+    /// as background. This doubles the vertical resolution, so that an aspect ratio of `1` draws a round circle.
+    /// Slices need a [`color`](struct.Data.html#structfield.color) with a foreground to be distinguishable.
+    HalfBlock,
+}
+
+/// Where the legend is placed relative to the pie, see [`Chart::legend_position`](struct.Chart.html#method.legend_position).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegendPosition {
+    /// The entries are listed in a column to the right of the pie.
+    Right,
+    /// The entries are listed in a column to the left of the pie.
+    Left,
+    /// The entries are listed above the pie, wrapped into as many columns as fit into its width.
+    Above,
+    /// The entries are listed below the pie, wrapped into as many columns as fit into its width.
+    Below,
+}
+
+/// How labels which are too long for the legend are shortened, see [`Chart::label_overflow`](struct.Chart.html#method.label_overflow).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelOverflow {
+    /// The end of the label is replaced with `…`.
+    Truncate,
+    /// The middle of the label is replaced with `…`, which keeps the end of e.g. file paths readable.
+    TruncateMiddle,
+    /// The label is wrapped onto the following lines, which are aligned with the start of the label.
+    Wrap,
+}
+
+/// The amount of columns and rows a chart is drawn into, see [`Chart::dimensions`](struct.Chart.html#method.dimensions).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dimensions {
+    /// The columns of the whole output, including the legend.
+    pub columns: usize,
+    /// The rows of the whole output, including the legend.
+    pub rows: usize,
+    /// The columns of the output without the legend.
+    pub pie_columns: usize,
+    /// The rows of the output without the legend.
+    pub pie_rows: usize,
+}
+
+/// The kind of outline drawn around the pie, see [`Chart::outline`](struct.Chart.html#method.outline).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outline {
+    /// No outline is drawn.
+    None,
+    /// The outline is drawn with line characters (`─`, `│`, `╱`, `╲`) following the edge.
+    Lines,
+    /// The outline is drawn with braille dots along the outer side of the edge cells.
+    Braille,
+}
+
+/// The text written into large slices, see [`Chart::slice_labels`](struct.Chart.html#method.slice_labels).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceLabel {
+    /// No text is written into the slices.
+    None,
+    /// The percentage of the slice, e.g. `47%`.
+    Percent,
+    /// The [`label`](struct.Data.html#structfield.label) of the slice.
+    Label,
+}
+
+/// How cells which are only partially covered by their slice are drawn in [`RenderMode::Fill`](enum.RenderMode.html#variant.Fill),
+/// see [`Chart::edge_style`](struct.Chart.html#method.edge_style).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeStyle {
+    /// Partially covered cells are drawn with the fill char like every other cell.
+    Fill,
+    /// Partially covered cells are drawn with the quadrant block (`▖`, `▚`, `▙`, ...) closest to the covered area.
+    Quadrants,
+    /// Partially covered cells are drawn with a shade (`░`, `▒`, `▓`) depending on how much of them is covered.
+    Shade,
+}
+
 /// The `Chart` struct contains the configuration for displaying some data.
 ///
 /// By default, a chart has a radius of `9`, an aspect ratio of `2` and doesn't show its legend.
@@ -28,16 +117,70 @@ const LABEL_PADDING: usize = 2;
 #[derive(Debug)]
 pub struct Chart {
     radius: u16,
-    aspect_ratio: u16,
+    size: Option<(u16, u16)>,
+    auto_size: bool,
+    aspect_ratio: f32,
+    hole_ratio: f32,
+    center_label: Option<String>,
+    explode: Vec<u16>,
+    start_angle: f32,
+    clockwise: bool,
+    render_mode: RenderMode,
+    edge_style: EdgeStyle,
+    supersampling: u16,
+    min_slice_cells: u16,
+    boosted_marker: Option<String>,
+    separators: bool,
+    separator_style: Option<Style>,
+    outline: Outline,
+    outline_style: Option<Style>,
+    slice_labels: SliceLabel,
+    slice_label_min_share: f32,
+    callouts: bool,
     legend: bool,
+    legend_position: LegendPosition,
+    legend_format: Option<LegendFormat>,
+    legend_table: bool,
+    legend_header: bool,
+    legend_width: Option<usize>,
+    label_overflow: LabelOverflow,
+    number_format: NumberFormat,
+    exact_percentages: bool,
     total: bool,
 }
 impl Default for Chart {
     fn default() -> Self {
         Self {
             radius: 8,
-            aspect_ratio: 3,
+            size: None,
+            auto_size: false,
+            aspect_ratio: 3.0,
+            hole_ratio: 0.0,
+            center_label: None,
+            explode: Vec::new(),
+            start_angle: 0.0,
+            clockwise: true,
+            render_mode: RenderMode::Fill,
+            edge_style: EdgeStyle::Fill,
+            supersampling: 1,
+            min_slice_cells: 0,
+            boosted_marker: None,
+            separators: false,
+            separator_style: None,
+            outline: Outline::None,
+            outline_style: None,
+            slice_labels: SliceLabel::None,
+            slice_label_min_share: 0.1,
+            callouts: false,
             legend: false,
+            legend_position: LegendPosition::Right,
+            legend_format: None,
+            legend_table: false,
+            legend_header: false,
+            legend_width: None,
+            label_overflow: LabelOverflow::Truncate,
+            number_format: NumberFormat::default(),
+            exact_percentages: false,
             total: false,
         }
     }
@@ -59,24 +202,262 @@ impl Chart {
     ///     chart.draw(&data);
     /// }
     /// ```
+    /// This replaces a [`size`](#method.size).
     pub fn radius(&mut self, radius: u16) -> &mut Self {
         self.radius = radius;
+        self.size = None;
+        self
+    }
+    /// Chooses the radius and aspect ratio so that the pie fills a box of `columns` and `rows`, instead of
+    /// the [`radius`](#method.radius) and [`aspect_ratio`](#method.aspect_ratio).
+    /// Because the pie has a center cell, it is one column or row smaller than an even size.
+    /// The legend, callouts and exploded slices need additional space, which
+    /// [`Chart::dimensions`](struct.Chart.html#method.dimensions) takes into account.
+    pub fn size(&mut self, columns: u16, rows: u16) -> &mut Self {
+        assert!(columns > 0 && rows > 0, "size has to be greater than zero");
+        self.size = Some((columns, rows));
+        self
+    }
+    /// Chooses the radius automatically when drawing, as the largest one with which the chart and its legend
+    /// fit into the terminal (see [`Chart::fit_radius`](struct.Chart.html#method.fit_radius)).
+    /// The size of the terminal is queried from stdout, or read from the `COLUMNS` and `LINES` environment variables
+    /// if stdout isn't a terminal. If neither is available, the [`size`](#method.size) or
+    /// [`radius`](#method.radius) is used.
+    pub fn auto_size(&mut self, auto_size: bool) -> &mut Self {
+        self.auto_size = auto_size;
         self
     }
     /// The aspect ratio controls how stretched or squished the circle is.
     /// Since terminal columns are more tall than wide a ration of 2 or 3 is the best in most cases.
-    pub fn aspect_ratio(&mut self, aspect_ratio: u16) -> &mut Self {
-        assert!(aspect_ratio > 0, "aspect ratio has to be greater than zero");
+    /// Fractional ratios like `2.4` can match the cells of a specific terminal more exactly.
+    /// This replaces a [`size`](#method.size).
+    // this takes `f64`, because integer literals are `i32`, which doesn't convert into `f32`
+    pub fn aspect_ratio(&mut self, aspect_ratio: impl Into<f64>) -> &mut Self {
+        let aspect_ratio = aspect_ratio.into() as f32;
+        assert!(
+            aspect_ratio > 0.0 && aspect_ratio.is_finite(),
+            "aspect ratio has to be greater than zero"
+        );
         self.aspect_ratio = aspect_ratio;
+        self.size = None;
+        self
+    }
+    /// Turns the pie into a donut by leaving the center empty.
+    /// The ratio is relative to the radius, so `0.5` cuts out a hole half as big as the whole chart.
+    pub fn hole_ratio(&mut self, hole_ratio: f32) -> &mut Self {
+        assert!(
+            (0.0..1.0).contains(&hole_ratio),
+            "hole ratio has to be in the range 0.0..1.0"
+        );
+        self.hole_ratio = hole_ratio;
+        self
+    }
+    /// Sets a text which is displayed in the middle of a donut chart (see [`Chart::hole_ratio`](struct.Chart.html#method.hole_ratio)).
+    /// A second line can be added by separating it with a `\n`. Lines which don't fit into the hole are truncated.
+    pub fn center_label(&mut self, center_label: impl Into<String>) -> &mut Self {
+        self.center_label = Some(center_label.into());
+        self
+    }
+    /// Pulls the slice at `index` out of the pie by `offset` rows, to emphasise it.
+    /// Calling this multiple times explodes multiple slices, an offset of `0` puts the slice back.
+    pub fn explode(&mut self, index: usize, offset: u16) -> &mut Self {
+        if self.explode.len() <= index {
+            self.explode.resize(index + 1, 0);
+        }
+        self.explode[index] = offset;
+        self
+    }
+
+    /// Sets the angle in degrees at which the first slice starts.
+    /// `0` is at the top of the chart (12 o'clock) and the angle is measured clockwise, `90` is at 3 o'clock.
+    pub fn start_angle(&mut self, start_angle: f32) -> &mut Self {
+        self.start_angle = start_angle;
+        self
+    }
+    /// Specifies whether the slices are drawn clockwise (the default) or counterclockwise from the start angle.
+    pub fn clockwise(&mut self, clockwise: bool) -> &mut Self {
+        self.clockwise = clockwise;
+        self
+    }
+
+    /// Sets how the area of the slices is drawn, by default [`RenderMode::Fill`](enum.RenderMode.html#variant.Fill).
+    pub fn render_mode(&mut self, render_mode: RenderMode) -> &mut Self {
+        self.render_mode = render_mode;
+        self
+    }
+
+    /// Sets how cells at the edge of the circle and between slices are drawn, by default [`EdgeStyle::Fill`](enum.EdgeStyle.html#variant.Fill).
+    /// The coverage of those cells is sampled with at least 4x4 points, or more if [`supersampling`](#method.supersampling) is higher.
+    pub fn edge_style(&mut self, edge_style: EdgeStyle) -> &mut Self {
+        self.edge_style = edge_style;
+        self
+    }
+
+    /// Sets how many points per row and column of a cell are sampled to decide which slice it belongs to.
+    /// By default only the center of a cell is sampled. With more samples, each cell gets the slice covering
+    /// most of it, which makes the slice boundaries and the edge of the circle smoother.
+    /// This only applies to [`RenderMode::Fill`](enum.RenderMode.html#variant.Fill) and
+    /// [`RenderMode::HalfBlock`](enum.RenderMode.html#variant.HalfBlock).
+    pub fn supersampling(&mut self, samples: u16) -> &mut Self {
+        assert!(samples > 0, "supersampling has to be greater than zero");
+        self.supersampling = samples;
+        self
+    }
+
+    /// Guarantees that every slice with a non-zero value covers at least `cells` cells,
+    /// so that small slices don't vanish from the pie while still appearing in the legend.
+    /// The missing cells are taken from the largest neighbouring slice.
+    pub fn min_slice_cells(&mut self, cells: u16) -> &mut Self {
+        self.min_slice_cells = cells;
+        self
+    }
+    /// Sets a marker which is appended to the legend entries of slices which
+    /// had to be enlarged because of [`min_slice_cells`](#method.min_slice_cells).
+    pub fn boosted_marker(&mut self, marker: impl Into<String>) -> &mut Self {
+        self.boosted_marker = Some(marker.into());
+        self
+    }
+
+    /// Specifies whether thin lines are drawn between adjacent slices,
+    /// so that slices with similar colors or the same fill char stay distinguishable.
+    pub fn separators(&mut self, separators: bool) -> &mut Self {
+        self.separators = separators;
+        self
+    }
+    /// Sets the style of the [`separators`](#method.separators). By default they don't have a color.
+    pub fn separator_style(&mut self, style: Style) -> &mut Self {
+        self.separator_style = Some(style);
+        self
+    }
+
+    /// Draws an outline around the pie, by default [`Outline::None`](enum.Outline.html#variant.None).
+    /// The outline replaces the outermost cells of the pie, so that the size of the chart stays the same.
+    pub fn outline(&mut self, outline: Outline) -> &mut Self {
+        self.outline = outline;
+        self
+    }
+    /// Sets the style of the [`outline`](#method.outline). By default it doesn't have a color.
+    pub fn outline_style(&mut self, style: Style) -> &mut Self {
+        self.outline_style = Some(style);
+        self
+    }
+
+    /// Writes the percentage or label of large slices directly into them, by default [`SliceLabel::None`](enum.SliceLabel.html#variant.None).
+    /// Slices whose text doesn't fit are listed in the legend, even if the [`legend`](#method.legend) is disabled.
+    pub fn slice_labels(&mut self, slice_labels: SliceLabel) -> &mut Self {
+        self.slice_labels = slice_labels;
+        self
+    }
+    /// Sets the share of the total (`0.0` to `1.0`) a slice needs to get a [`slice label`](#method.slice_labels), by default `0.1`.
+    pub fn slice_label_min_share(&mut self, min_share: f32) -> &mut Self {
+        self.slice_label_min_share = min_share;
+        self
+    }
+
+    /// Places the label of each slice outside of the pie next to the slice, connected to it by a leader line.
+    /// The callouts replace the [`legend`](#method.legend) and skip slices which got a [`slice label`](#method.slice_labels).
+    pub fn callouts(&mut self, callouts: bool) -> &mut Self {
+        self.callouts = callouts;
         self
     }
 
     /// Specifies whether the chart should render a legend with the labels and their percentages.
+    /// If there are more entries than rows next to the pie, they are moved closer together and continue below it.
     pub fn legend(&mut self, legend: bool) -> &mut Self {
         self.legend = legend;
         self
     }
 
+    /// Sets where the legend is placed, by default [`LegendPosition::Right`](enum.LegendPosition.html#variant.Right).
+    pub fn legend_position(&mut self, position: LegendPosition) -> &mut Self {
+        self.legend_position = position;
+        self
+    }
+
+    /// Sets a template for the legend entries, by default they look like `{fill} {label} {percent}% ({value})`.
+    /// The placeholders `{fill}`, `{label}`, `{value}`, `{percent}`, `{cumulative}` (the cumulative percentage)
+    /// and `{rank}` (`1` for the largest value) can be formatted like in `format!`, e.g. `{label:<12}` or `{percent:>6.1}`.
+    /// The percentages have two decimal places unless a precision is given. `{{` and `}}` are written as `{` and `}`.
+    ///
+    /// Panics if the template contains an unknown placeholder or an invalid format spec.
+    /// ```rust
+    /// # use piechart::{Chart, Data};
+    /// # let data = vec![Default::default()];
+    /// Chart::new()
+    ///     .legend(true)
+    ///     .legend_format("{rank}. {fill} {label:<12} {percent:>6.1}% {value}")
+    ///     .draw(&data);
+    /// ```
+    pub fn legend_format(&mut self, template: &str) -> &mut Self {
+        self.legend_format = Some(LegendFormat::template(template));
+        self
+    }
+    /// Formats the legend entries with a closure, for formatting which isn't possible with a [`legend_format`](#method.legend_format).
+    pub fn legend_formatter(
+        &mut self,
+        formatter: impl Fn(&LegendEntry<'_>) -> String + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.legend_format = Some(LegendFormat::Closure(Box::new(formatter)));
+        self
+    }
+
+    /// Renders the legend as a table whose label, percentage and value columns are aligned.
+    /// The table ignores the [`legend_format`](#method.legend_format) and puts the [`total`](#method.total)
+    /// into its last row, below a separator line.
+    pub fn legend_table(&mut self, table: bool) -> &mut Self {
+        self.legend_table = table;
+        self
+    }
+    /// Specifies whether the [`legend_table`](#method.legend_table) starts with a row naming its columns.
+    pub fn legend_header(&mut self, header: bool) -> &mut Self {
+        self.legend_header = header;
+        self
+    }
+
+    /// Sets the maximum width of the legend entries in columns. The labels of wider entries are shortened
+    /// or wrapped depending on the [`label_overflow`](#method.label_overflow).
+    /// This doesn't apply to entries formatted by a [`legend_formatter`](#method.legend_formatter).
+    pub fn legend_width(&mut self, width: usize) -> &mut Self {
+        self.legend_width = Some(width);
+        self
+    }
+    /// Sets how labels which don't fit into the [`legend_width`](#method.legend_width) are shortened,
+    /// by default [`LabelOverflow::Truncate`](enum.LabelOverflow.html#variant.Truncate).
+    pub fn label_overflow(&mut self, overflow: LabelOverflow) -> &mut Self {
+        self.label_overflow = overflow;
+        self
+    }
+
+    /// Sets the amount of decimal places of the values and the total in the legend.
+    /// By default they are written with as many decimal places as needed.
+    pub fn precision(&mut self, precision: usize) -> &mut Self {
+        self.number_format.precision = Some(precision);
+        self
+    }
+    /// Sets a separator which is inserted between every three digits of the values and the total, e.g. `,` for `1,000,000`.
+    pub fn thousands_separator(&mut self, separator: char) -> &mut Self {
+        self.number_format.thousands_separator = Some(separator);
+        self
+    }
+    /// Scales large values and the total and adds a suffix like `k` or `Ki`, by default [`UnitPrefix::None`](enum.UnitPrefix.html#variant.None).
+    pub fn unit_prefix(&mut self, prefix: UnitPrefix) -> &mut Self {
+        self.number_format.prefix = prefix;
+        self
+    }
+    /// Sets a unit which is appended to the values and the total, after the [`unit_prefix`](#method.unit_prefix).
+    /// For example `" ms"` gives `12.5 ms` and `"B"` with a binary prefix gives `1.5KiB`.
+    pub fn unit(&mut self, unit: impl Into<String>) -> &mut Self {
+        self.number_format.unit = unit.into();
+        self
+    }
+
+    /// Rounds the displayed percentages with the largest remainder method, so that they always add up
+    /// to exactly 100% at the precision they are displayed with, instead of e.g. 99.99% or 100.01%.
+    pub fn exact_percentages(&mut self, exact: bool) -> &mut Self {
+        self.exact_percentages = exact;
+        self
+    }
+
     /// Specifies whether the chart should show a total sum of data values in its legend
     pub fn total(&mut self, total: bool) -> &mut Self {
         self.total = total;
@@ -84,6 +465,562 @@ impl Chart {
     }
 }
 
+impl Chart {
+    /// Computes how much of each cell of the pie is covered by each slice, row by row.
+    /// This uses the [`supersampling`](#method.supersampling) setting, so that it can be used
+    /// to implement other ways of rendering the chart. The cells are the ones
+    /// [`Chart::draw_into`](struct.Chart.html#method.draw_into) writes, so in
+    /// [`RenderMode::HalfBlock`](enum.RenderMode.html#variant.HalfBlock) each cell covers two rows of the pie.
+    pub fn coverage(&self, data: &[Data]) -> Vec<Vec<Coverage>> {
+        let total: f32 = data.iter().map(|d| d.value).sum();
+        let geometry = self.geometry(total, data, self.draw_shape(data));
+        let (center_x, center_y) = geometry.center();
+        let (width, height) = geometry.size();
+
+        let rows: Vec<Vec<Coverage>> = (0..height as i32)
+            .map(|row| {
+                (0..width as i32)
+                    .map(|col| {
+                        let (x, y) = (col - center_x, row - center_y);
+                        geometry.coverage_at(x, y, self.supersampling)
+                    })
+                    .collect()
+            })
+            .collect();
+        // in half-block mode, each cell consists of two rows of the geometry
+        let rows_per_cell = self.rows_per_cell() as usize;
+        rows.chunks(rows_per_cell)
+            .map(|rows| {
+                (0..width)
+                    .map(|col| Coverage::combine(rows.iter().map(|row| &row[col]), rows_per_cell))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn slice_at(&self, geometry: &Geometry, x: i32, y: i32) -> Option<usize> {
+        match (self.render_mode, self.supersampling) {
+            // partially covered cells at the edge of the circle are drawn too
+            (RenderMode::Fill, _) if self.edge_style != EdgeStyle::Fill => {
+                let samples = self.edge_samples();
+                let samples = geometry.samples_at(x, y, samples, samples);
+                utils::most_common(samples.into_iter().flatten())
+            }
+            (RenderMode::Fill, 1) | (RenderMode::HalfBlock, 1) => geometry.slice_at(x, y),
+            (RenderMode::Fill, samples) | (RenderMode::HalfBlock, samples) => {
+                geometry.coverage_at(x, y, samples).dominant()
+            }
+            (RenderMode::Braille, _) => {
+                let dots = geometry.samples_at(x, y, 2, 4);
+                utils::most_common(dots.into_iter().flatten())
+            }
+        }
+    }
+
+    fn cell(&self, geometry: &Geometry, x: i32, y: i32, item: &Data, idx: usize) -> Cell {
+        match self.render_mode {
+            RenderMode::Fill => {
+                let symbol = self.edge_symbol(geometry, x, y, idx);
+                Cell::new(symbol.unwrap_or(item.fill), item.color)
+            }
+            RenderMode::HalfBlock => unreachable!("half blocks are drawn by `half_block`"),
+            RenderMode::Braille => {
+                let dots = geometry.samples_at(x, y, 2, 4);
+                let mut set: Vec<bool> = dots.iter().map(|dot| *dot == Some(idx)).collect();
+                // the cell may have been given to a slice which doesn't cover it by `min_slice_cells`
+                if !set.contains(&true) {
+                    set = dots.iter().map(Option::is_some).collect();
+                }
+                Cell::new(utils::braille(&set), item.color)
+            }
+        }
+    }
+
+    /// Returns the symbol for a cell which is only partially covered by the slice at `idx`, depending on the edge style.
+    fn edge_symbol(&self, geometry: &Geometry, x: i32, y: i32, idx: usize) -> Option<char> {
+        let samples = self.edge_samples();
+        match self.edge_style {
+            EdgeStyle::Fill => None,
+            EdgeStyle::Quadrants => {
+                let half = samples / 2;
+                let mut quadrants = [0; 4];
+                let cells = geometry.samples_at(x, y, samples, samples);
+                for (i, _) in cells.iter().enumerate().filter(|(_, s)| **s == Some(idx)) {
+                    let (col, row) = (i as u16 % samples, i as u16 / samples);
+                    quadrants[(row / half * 2 + col / half) as usize] += 1;
+                }
+                let mut set = quadrants.map(|count| count * 2 >= half * half);
+                // cells which are barely covered at least get the quadrant covered the most
+                let max = (0..4).max_by_key(|i| quadrants[*i]).unwrap();
+                set[max] |= quadrants[max] > 0;
+                match set {
+                    [true, true, true, true] | [false, false, false, false] => None,
+                    set => Some(utils::quadrant_block(set)),
+                }
+            }
+            EdgeStyle::Shade => {
+                let fraction = geometry.coverage_at(x, y, samples).fraction(idx);
+                match fraction {
+                    f if f < 0.375 => Some('░'),
+                    f if f < 0.625 => Some('▒'),
+                    f if f < 0.875 => Some('▓'),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Writes the slice labels into the slices, returns which slices were labeled.
+    fn draw_slice_labels(
+        &self,
+        canvas: &mut Canvas,
+        geometry: &Geometry,
+        slices: &[Vec<Option<usize>>],
+        data: &[Data],
+        total: f32,
+    ) -> Vec<bool> {
+        let rows_per_cell = self.rows_per_cell();
+        let (center_x, center_y) = geometry.center();
+        let mut labeled = vec![false; data.len()];
+        let percentages = self.percentages(data, total);
+
+        for (idx, item) in data.iter().enumerate() {
+            let share = item.value / total;
+            if share <= 0.0 || share < self.slice_label_min_share {
+                continue;
+            }
+            let text = match self.slice_labels {
+                SliceLabel::None => return labeled,
+                SliceLabel::Percent => format!("{:.0}%", percentages.get(idx, 0)),
+                SliceLabel::Label => item.label.clone(),
+            };
+
+            let (x, y) = geometry.centroid(idx);
+            let len = utils::text_width(&text) as i32;
+            let start = (x + center_x as f32).round() as i32 - (len - 1) / 2;
+            let row = ((y.round() as i32 + center_y) / rows_per_cell) as usize;
+
+            // every cell the text is written into has to belong to the slice
+            let fits = (start..start + len).all(|col| {
+                let rows = row * rows_per_cell as usize..(row + 1) * rows_per_cell as usize;
+                let lines = slices.get(rows).unwrap_or_default();
+                col >= 0
+                    && !lines.is_empty()
+                    && lines
+                        .iter()
+                        .all(|line| line.get(col as usize) == Some(&Some(idx)))
+            });
+            if !fits || len == 0 {
+                continue;
+            }
+
+            let style = item
+                .color
+                .and_then(|style| style.foreground)
+                .map(|color| Style::new().fg(utils::contrast(color)).on(color));
+            canvas.text(start as usize, row, &text, style);
+            labeled[idx] = true;
+        }
+        labeled
+    }
+
+    /// Draws the callouts of the slices at `entries` next to the pie on `canvas` and returns the enlarged canvas.
+    fn draw_callouts(
+        &self,
+        canvas: &Canvas,
+        geometry: &Geometry,
+        data: &[Data],
+        total: f32,
+        entries: &[usize],
+    ) -> Canvas {
+        let rows_per_cell = self.rows_per_cell();
+        let (center_x, center_y) = geometry.center();
+        let (width, height) = (canvas.width() as i32, canvas.height() as i32);
+        let percentages = self.percentages(data, total);
+
+        // the leader lines start just outside of the pie on the bisector of their slice
+        let anchors: Vec<(usize, i32, i32, f32)> = entries
+            .iter()
+            .map(|&idx| {
+                let distance = (geometry.radius + rows_per_cell) as f32;
+                let (x, y) = geometry.bisector_point(idx, distance);
+                let col = x.round() as i32 + center_x;
+                let row = (y.round() as i32 + center_y).div_euclid(rows_per_cell);
+                (idx, col, row, y)
+            })
+            .collect();
+
+        let mut leaders = Vec::new();
+        let mut labels = Vec::new();
+        for &side in &[1, -1] {
+            let mut anchors: Vec<_> = anchors
+                .iter()
+                .copied()
+                .filter(|(_, col, _, _)| (*col >= center_x) == (side == 1))
+                .collect();
+            anchors.sort_by(|(_, _, _, a), (_, _, _, b)| a.partial_cmp(b).unwrap());
+
+            // move labels apart until every one has its own row, staying as close to the slices as possible
+            let mut rows: Vec<i32> = anchors.iter().map(|(_, _, row, _)| *row).collect();
+            let last = rows.iter().copied().fold(height - 1, i32::max);
+            for i in 1..rows.len() {
+                rows[i] = rows[i].max(rows[i - 1] + 1);
+            }
+            for i in (0..rows.len()).rev() {
+                let max = rows.get(i + 1).map_or(last, |row| row - 1);
+                rows[i] = rows[i].min(max);
+            }
+
+            // the labels are aligned in a column next to the pie and the furthest anchor
+            let column = match side {
+                1 => {
+                    anchors
+                        .iter()
+                        .map(|(_, col, _, _)| *col)
+                        .fold(width - 1, i32::max)
+                        + LABEL_PADDING as i32
+                        + 1
+                }
+                _ => {
+                    anchors.iter().map(|(_, col, _, _)| *col).fold(0, i32::min)
+                        - LABEL_PADDING as i32
+                        - 1
+                }
+            };
+            for (&(idx, mut col, mut row, _), &target) in anchors.iter().zip(&rows) {
+                let end = column - side * 2;
+                while row != target {
+                    let step = (target - row).signum();
+                    let diagonal = (end - col) * side > 0;
+                    let symbol = match (diagonal, step == side) {
+                        (false, _) => '│',
+                        (true, true) => '╲',
+                        (true, false) => '╱',
+                    };
+                    leaders.push((col, row, symbol));
+                    row += step;
+                    if diagonal {
+                        col += side;
+                    }
+                }
+                while (end - col) * side >= 0 {
+                    leaders.push((col, row, '─'));
+                    col += side;
+                }
+
+                let item = &data[idx];
+                let mut cells = vec![Cell::new(item.fill, item.color), Cell::new(' ', None)];
+                let text = item.format_callout(percentages.get(idx, 2));
+                cells.extend(text.chars().map(|c| Cell::new(c, None)));
+                // wide chars cover the cell after them
+                let cells: Vec<Cell> = cells
+                    .into_iter()
+                    .flat_map(|cell| {
+                        let covered = cell.width().saturating_sub(1);
+                        iter::once(cell).chain(iter::repeat_n(Cell::new(' ', None), covered))
+                    })
+                    .collect();
+                let start = match side {
+                    1 => column,
+                    _ => column - cells.len() as i32 + 1,
+                };
+                labels.push((start, row, cells));
+            }
+        }
+
+        let label_cells = labels.iter().flat_map(|(start, row, cells)| {
+            (0..cells.len() as i32).map(move |i| (start + i, *row))
+        });
+        let cells = leaders
+            .iter()
+            .map(|(col, row, _)| (*col, *row))
+            .chain(label_cells);
+        let (left, right, top, bottom) = cells.fold(
+            (0, width - 1, 0, height - 1),
+            |(left, right, top, bottom), (col, row)| {
+                (left.min(col), right.max(col), top.min(row), bottom.max(row))
+            },
+        );
+
+        let mut callouts = Canvas::new((right - left + 1) as usize, (bottom - top + 1) as usize);
+        for row in 0..height {
+            for col in 0..width {
+                if let Some(cell) = canvas.get(col as usize, row as usize) {
+                    callouts.set((col - left) as usize, (row - top) as usize, cell);
+                }
+            }
+        }
+        for (col, row, symbol) in leaders {
+            let (col, row) = ((col - left) as usize, (row - top) as usize);
+            // leader lines pass behind the pie
+            if callouts.get(col, row).is_none() {
+                callouts.set(col, row, Cell::new(symbol, None));
+            }
+        }
+        for (start, row, cells) in labels {
+            for (i, cell) in cells.into_iter().enumerate() {
+                callouts.set((start - left) as usize + i, (row - top) as usize, cell);
+            }
+        }
+        callouts
+    }
+
+    /// Formats the entries at `legend` as the rows of a table with aligned columns.
+    fn legend_table_rows(
+        &self,
+        data: &[Data],
+        legend: &[usize],
+        boosted: &[bool],
+        total: f32,
+    ) -> Vec<String> {
+        let percentages = self.percentages(data, total);
+        let items: Vec<[String; 4]> = legend
+            .iter()
+            .map(|&idx| {
+                let item = &data[idx];
+                let fill = match item.color {
+                    Some(c) => c.paint(item.fill.to_string()).to_string(),
+                    None => item.fill.to_string(),
+                };
+                let percent = format!("{:.2}%", percentages.get(idx, 2));
+                [
+                    fill,
+                    item.label.clone(),
+                    percent,
+                    self.number_format.format(item.value),
+                ]
+            })
+            .collect();
+        let header = [" ", "Label", "Percent", "Value"].map(String::from);
+        let total = [" ", "Total", "100.00%", &self.number_format.format(total)].map(String::from);
+
+        let mut rows: Vec<&[String; 4]> = items.iter().collect();
+        if self.legend_header {
+            rows.push(&header);
+        }
+        if self.total {
+            rows.push(&total);
+        }
+        let width = |column: usize| {
+            let widths = rows.iter().map(|row| utils::text_width(&row[column]));
+            widths.max().unwrap_or(0)
+        };
+        let mut widths = [width(0), width(1), width(2), width(3)];
+        // the label column is narrowed until the table fits into the legend width
+        if let Some(max_width) = self.legend_width {
+            let table_width = widths.iter().sum::<usize>() + 5;
+            widths[1] = (widths[1] + max_width).saturating_sub(table_width).max(1);
+        }
+
+        let line = |fill: &str, label: &str, percent: &str, value: &str| {
+            let line = format!(
+                "{}{} {}{}  {}{}  {}{}",
+                fill,
+                " ".repeat(widths[0] - utils::text_width(fill)),
+                label,
+                " ".repeat(widths[1].saturating_sub(utils::text_width(label))),
+                " ".repeat(widths[2] - utils::text_width(percent)),
+                percent,
+                " ".repeat(widths[3] - utils::text_width(value)),
+                value,
+            );
+            line.trim_end().to_string()
+        };
+
+        let mut lines = Vec::new();
+        if self.legend_header {
+            let label = utils::truncate_end(&header[1], widths[1]);
+            lines.push(line(&header[0], &label, &header[2], &header[3]));
+        }
+        for ([fill, label, percent, value], &idx) in items.iter().zip(legend) {
+            let mut labels = self.fit_label(label, widths[1]).into_iter();
+            let label = labels.next().unwrap_or_default();
+            let mut first = line(fill, &label, percent, value);
+            if let (true, Some(marker)) = (boosted[idx], &self.boosted_marker) {
+                first.push(' ');
+                first.push_str(marker);
+            }
+            lines.push(first);
+            lines.extend(labels.map(|label| line(" ", &label, "", "")));
+        }
+        if self.total {
+            lines.push("─".repeat(widths.iter().sum::<usize>() + 5));
+            let label = utils::truncate_end(&total[1], widths[1]);
+            lines.push(line(&total[0], &label, &total[2], &total[3]));
+        }
+        lines
+    }
+
+    /// Shortens or wraps `label` to `width` columns, depending on the [`label_overflow`](#method.label_overflow).
+    fn fit_label(&self, label: &str, width: usize) -> Vec<String> {
+        match self.label_overflow {
+            _ if utils::text_width(label) <= width => vec![label.to_string()],
+            LabelOverflow::Truncate => vec![utils::truncate_end(label, width)],
+            LabelOverflow::TruncateMiddle => vec![utils::truncate_middle(label, width)],
+            LabelOverflow::Wrap => utils::wrap(label, width),
+        }
+    }
+
+    /// Formats the legend entry of a data item with the label `label` by calling `format`,
+    /// shortening or wrapping the label if the entry is wider than the [`legend_width`](#method.legend_width).
+    /// Wrapped labels continue on the next lines, aligned with the first one.
+    fn fit_legend_entry(&self, label: &str, format: impl Fn(&str) -> String) -> String {
+        let entry = format(label);
+        let max_width = match self.legend_width {
+            Some(max_width) if utils::text_width(&entry) > max_width => max_width,
+            _ => return entry,
+        };
+        // entries which don't contain the label can't be shortened
+        if !format("\0").contains('\0') {
+            return entry;
+        }
+
+        // the label is shortened until the entry fits. If it never does, truncated labels keep
+        // at least `…` and wrapped labels are left as they are
+        let fits = |lines: &[String]| {
+            lines
+                .iter()
+                .all(|line| utils::text_width(line) <= max_width)
+        };
+        let labels = (1..utils::text_width(label))
+            .rev()
+            .map(|width| self.fit_label(label, width))
+            .find(|labels| fits(&Self::legend_entry_lines(labels, &format)))
+            .unwrap_or_else(|| match self.label_overflow {
+                LabelOverflow::Wrap => vec![label.to_string()],
+                _ => self.fit_label(label, 1),
+            });
+        Self::legend_entry_lines(&labels, &format).join("\n")
+    }
+
+    /// Formats an entry whose label is split into `labels`, the first one is formatted with `format`
+    /// and the following ones are put on the next lines, starting in the same column as the first one.
+    fn legend_entry_lines(labels: &[String], format: impl Fn(&str) -> String) -> Vec<String> {
+        let first = labels.first().map_or("", String::as_str);
+        // the start of the label is found by marking it, which doesn't change the width of the entry
+        let marked = format(&format!("\0{}", first));
+        let column = marked
+            .find('\0')
+            .map_or(0, |i| utils::text_width(&marked[..i]));
+        let rest = labels.iter().skip(1);
+        iter::once(format(first))
+            .chain(rest.map(|label| format!("{}{}", " ".repeat(column), label)))
+            .collect()
+    }
+
+    /// Returns the outline symbol of the canvas cell at (`col`, `row`) if it is on the edge of the pie.
+    fn outline_symbol(
+        &self,
+        geometry: &Geometry,
+        col: usize,
+        row: usize,
+        center_x: i32,
+        center_y: i32,
+    ) -> Option<char> {
+        let rows_per_cell = self.rows_per_cell();
+        let x = col as i32 - center_x;
+        let rows =
+            (0..rows_per_cell).map(|sub_row| row as i32 * rows_per_cell + sub_row - center_y);
+
+        match self.outline {
+            Outline::None => None,
+            Outline::Lines => {
+                let y = rows
+                    .into_iter()
+                    .find(|y| geometry.outline_at(x, *y).is_some())?;
+                Some(self.line_symbol(geometry.tangent_at(x, y)))
+            }
+            Outline::Braille => {
+                // each row of the geometry gets the same amount of the 4 rows of braille dots
+                let dot_rows = 4 / rows_per_cell as usize;
+                let mut dots = [false; 8];
+                for (sub_row, y) in rows.enumerate() {
+                    let [above, below, left, right] = match geometry.outline_at(x, y) {
+                        Some(sides) => sides,
+                        None => continue,
+                    };
+                    let (first, last) = (sub_row * dot_rows, (sub_row + 1) * dot_rows - 1);
+                    for dot_row in first..=last {
+                        dots[dot_row * 2] |=
+                            left || (above && dot_row == first) || (below && dot_row == last);
+                        dots[dot_row * 2 + 1] |=
+                            right || (above && dot_row == first) || (below && dot_row == last);
+                    }
+                }
+                match dots.contains(&true) {
+                    true => Some(utils::braille(&dots)),
+                    false => None,
+                }
+            }
+        }
+    }
+
+    /// The amount of samples per row and column used for edges, which is even so that it can be split into quadrants.
+    fn edge_samples(&self) -> u16 {
+        self.supersampling.max(4) / 2 * 2
+    }
+
+    fn half_block(
+        &self,
+        data: &[Data],
+        upper: Option<usize>,
+        lower: Option<usize>,
+    ) -> Option<Cell> {
+        let style = |idx: usize| data[idx].color.unwrap_or_default();
+        let (symbol, style) = match (upper, lower) {
+            (None, None) => return None,
+            (Some(upper), None) => ('▀', style(upper)),
+            (None, Some(lower)) => ('▄', style(lower)),
+            (Some(upper), Some(lower)) if upper == lower => ('█', style(upper)),
+            (Some(upper), Some(lower)) => match style(lower).foreground {
+                Some(background) => ('▀', style(upper).on(background)),
+                None => ('▀', style(upper)),
+            },
+        };
+        match style.is_plain() {
+            true => Some(Cell::new(symbol, None)),
+            false => Some(Cell::new(symbol, Some(style))),
+        }
+    }
+
+    fn percentages<'a>(&self, data: &'a [Data], total: f32) -> Percentages<'a> {
+        Percentages::new(data, total, self.exact_percentages)
+    }
+
+    /// Returns the line char closest to the direction `dir`, which is given in rows of the geometry.
+    fn line_symbol(&self, (dir_x, dir_y): (f32, f32)) -> char {
+        // assume cells are twice as high as wide
+        utils::line_symbol(dir_x, dir_y * 2.0 / self.rows_per_cell() as f32)
+    }
+
+    /// The amount of rows the geometry has per row of the output.
+    fn rows_per_cell(&self) -> i32 {
+        match self.render_mode {
+            RenderMode::HalfBlock => 2,
+            RenderMode::Fill | RenderMode::Braille => 1,
+        }
+    }
+
+    /// `shape` is the radius and aspect ratio the chart is drawn with.
+    fn geometry(&self, total: f32, data: &[Data], (radius, aspect_ratio): (u16, f32)) -> Geometry {
+        let scale = self.rows_per_cell();
+        let mut geometry = Geometry::new(
+            radius as i32 * scale,
+            aspect_ratio,
+            self.hole_ratio,
+            self.start_angle,
+            self.clockwise,
+            utils::data_angles(total, data),
+        );
+        for (idx, offset) in self.explode.iter().enumerate().take(data.len()) {
+            if *offset > 0 {
+                geometry.explode(idx, (*offset as i32 * scale) as f32);
+            }
+        }
+        geometry
+    }
+}
+
 impl Chart {
     /// Renders the chart and outputs it onto `stdout`.
     /// The method panics in case of an error. If you want more fine-grained control about error recovery
@@ -95,77 +1032,353 @@ impl Chart {
 
     /// Same as [`Chart::draw`](struct.Chart.html#method.draw), but you can supply your own `impl Write`
     /// and you can handle errors gracefully.
+    pub fn draw_into(&self, mut f: impl io::Write, data: &[Data]) -> io::Result<()> {
+        for line in self.render(data, self.draw_shape(data), true) {
+            writeln!(&mut f, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the exact amount of columns and rows [`Chart::draw_into`](struct.Chart.html#method.draw_into)
+    /// writes for `data`, with and without the legend.
+    pub fn dimensions(&self, data: &[Data]) -> Dimensions {
+        let shape = self.draw_shape(data);
+        let size = |lines: Vec<String>| {
+            let columns = lines.iter().map(|line| utils::text_width(line)).max();
+            (columns.unwrap_or(0), lines.len())
+        };
+        let (columns, rows) = size(self.render(data, shape, true));
+        let (pie_columns, pie_rows) = size(self.render(data, shape, false));
+        Dimensions {
+            columns,
+            rows,
+            pie_columns,
+            pie_rows,
+        }
+    }
+
+    /// Returns the largest radius with which the output of [`Chart::draw_into`](struct.Chart.html#method.draw_into),
+    /// including the legend, fits into `columns` and `rows`. If not even a radius of `1` fits, `0` is returned.
+    /// The other settings of the chart are kept, so e.g. a larger aspect ratio leads to a smaller radius.
+    pub fn fit_radius(&self, data: &[Data], columns: u16, rows: u16) -> u16 {
+        let fits = |radius: u16| {
+            let lines = self.render(data, (radius, self.aspect_ratio), true);
+            let width = lines.iter().map(|line| utils::text_width(line)).max();
+            lines.len() <= rows as usize && width.unwrap_or(0) <= columns as usize
+        };
+        // the output grows with the radius, so the largest one that fits is searched for in between
+        // `low`, which fits, and `high`. The chart is at least `radius` rows high in every render mode
+        let (mut low, mut high) = (0, rows);
+        while low < high {
+            let radius = high - (high - low) / 2;
+            match fits(radius) {
+                true => low = radius,
+                false => high = radius - 1,
+            }
+        }
+        low
+    }
+
+    /// The radius and aspect ratio the chart is drawn with. They are chosen to fit into the terminal with
+    /// [`auto_size`](#method.auto_size), or into the box set with [`size`](#method.size).
+    fn draw_shape(&self, data: &[Data]) -> (u16, f32) {
+        let terminal = match self.auto_size {
+            true => terminal::size(),
+            false => None,
+        };
+        match (terminal, self.size) {
+            (Some((columns, rows)), _) => (self.fit_radius(data, columns, rows), self.aspect_ratio),
+            (None, Some((columns, rows))) => self.box_shape(columns, rows),
+            (None, None) => (self.radius, self.aspect_ratio),
+        }
+    }
+
+    /// The radius and aspect ratio with which the pie fills a box of `columns` and `rows`.
+    fn box_shape(&self, columns: u16, rows: u16) -> (u16, f32) {
+        let radius = (rows - 1) / 2;
+        let scaled_radius = radius as i32 * self.rows_per_cell();
+        if scaled_radius == 0 {
+            return (radius, self.aspect_ratio);
+        }
+        // the pie is `2 * calculate_center_x(radius, aspect_ratio) + 1` columns wide.
+        // A pie which is only one column wide still needs an aspect ratio greater than zero
+        let half_width = (((columns - 1) / 2) as f32).max(0.25);
+        (radius, (half_width / scaled_radius as f32).powi(2))
+    }
+
     // it works like this:
     //
-    // for each y from -radius to radius:
-    //   calculate the width of the circle at that height
-    //   write (center_x - width) padding spaces
-    //   for x from -width to width:
-    //     calculate the angle of the circle the point is in
-    //     depending on the angle, choose a datapoint
-    //     depending on that datapoint, print its fill symbol and color
-    pub fn draw_into(&self, mut f: impl io::Write, data: &[Data]) -> io::Result<()> {
+    // for each cell of the canvas (the circle plus the space exploded slices were moved into):
+    //   find the slice covering the cell, taking exploded slices into account
+    // give slices which are too small cells of their neighbours
+    // for each cell of the canvas:
+    //   depending on its datapoint(s), draw its fill symbol and color
+    // draw separators over the cells on the boundaries between slices
+    // draw the outline over the cells on the edge of the circle
+    // write the labels of large slices into their centers
+    // draw the center label into the hole
+    // place the callouts next to the pie, moving them apart so that they don't overlap
+    // for each row of the canvas:
+    //   write the row and the legend entry at that height, if the legend is on the left or right
+    // write the legend entries above or below the rows, wrapped into columns
+    fn render(&self, data: &[Data], shape: (u16, f32), with_legend: bool) -> Vec<String> {
         let total: f32 = data.iter().map(|d| d.value).sum();
         assert!(!data.is_empty(), "chart data cannot be empty");
         assert!(
             total > 0.0,
             "total of data values has to be greater than zero"
         );
-        let data_angles = utils::data_angles(total, data);
-
-        let radius = self.radius as i32;
-        let aspect_ratio = self.aspect_ratio as i32;
 
-        let center_x = utils::calculate_center_x(radius, aspect_ratio);
+        let geometry = self.geometry(total, data, shape);
+        let (center_x, center_y) = geometry.center();
+        let (width, height) = geometry.size();
+        let rows_per_cell = self.rows_per_cell();
+        let mut canvas = Canvas::new(width, height.div_ceil(rows_per_cell as usize));
 
-        let circle = (-radius..=radius).map(|y| {
-            let width = utils::calculate_width(radius, y, aspect_ratio);
-            let padding_len = (center_x - width) as usize;
+        let mut slices: Vec<Vec<Option<usize>>> = (0..height as i32)
+            .map(|row| {
+                (0..width as i32)
+                    .map(|col| self.slice_at(&geometry, col - center_x, row - center_y))
+                    .collect()
+            })
+            .collect();
+        let boosted = geometry.ensure_visible(&mut slices, self.min_slice_cells as usize);
 
-            let mut output = " ".repeat(padding_len);
-
-            (-width..=width).for_each(|x| {
-                let (x, y) = (x as f32, y as f32);
-                let angle = x.atan2(y).to_degrees();
+        match self.render_mode {
+            RenderMode::HalfBlock => {
+                for (row, lines) in slices.chunks(2).enumerate() {
+                    for col in 0..width {
+                        let lower = lines.get(1).and_then(|line| line[col]);
+                        if let Some(cell) = self.half_block(data, lines[0][col], lower) {
+                            canvas.set(col, row, cell);
+                        }
+                    }
+                }
+            }
+            RenderMode::Fill | RenderMode::Braille => {
+                for (row, line) in slices.iter().enumerate() {
+                    let mut covered = false;
+                    for (col, idx) in line.iter().enumerate() {
+                        if std::mem::take(&mut covered) {
+                            continue;
+                        }
+                        if let Some(idx) = *idx {
+                            let (x, y) = (col as i32 - center_x, row as i32 - center_y);
+                            let mut cell = self.cell(&geometry, x, y, &data[idx], idx);
+                            // wide fill chars are only drawn at even columns whose next cell belongs to
+                            // the same slice, which halves their density. Other cells of the slice stay blank
+                            if cell.width() > 1 {
+                                covered = col % 2 == 0 && line.get(col + 1) == Some(&Some(idx));
+                                if !covered {
+                                    cell = Cell::new(' ', None);
+                                }
+                            }
+                            canvas.set(col, row, cell);
+                        }
+                    }
+                }
+            }
+        }
 
-                let idx = data_angles
-                    .iter()
-                    .position(|a| 360.0 / 2.0 - angle <= *a)
-                    .expect("no data item for given angle, should be impossible");
-                let item = &data[idx];
+        if self.separators {
+            for (row, line) in slices.iter().enumerate() {
+                for (col, idx) in line.iter().enumerate() {
+                    let (x, y) = (col as i32 - center_x, row as i32 - center_y);
+                    let angle = idx.and_then(|idx| geometry.boundary_at(x, y, idx));
+                    if let Some(angle) = angle {
+                        let symbol = self.line_symbol(geometry.point_at(angle, 1.0));
+                        let cell = Cell::new(symbol, self.separator_style);
+                        canvas.set(col, row / rows_per_cell as usize, cell);
+                    }
+                }
+            }
+        }
 
-                match item.color {
-                    None => output.push(item.fill),
-                    Some(c) => output.push_str(&c.paint(item.fill.to_string()).to_string()),
+        if self.outline != Outline::None {
+            for row in 0..canvas.height() {
+                for col in 0..width {
+                    if let Some(symbol) =
+                        self.outline_symbol(&geometry, col, row, center_x, center_y)
+                    {
+                        canvas.set(col, row, Cell::new(symbol, self.outline_style));
+                    }
                 }
-            });
+            }
+        }
 
-            if self.legend {
-                output.push_str(&" ".repeat(padding_len + LABEL_PADDING));
+        let labeled = self.draw_slice_labels(&mut canvas, &geometry, &slices, data, total);
 
-                let max_label_idx = data.len() as i32;
+        let center_row = center_y / rows_per_cell;
+        if let Some(center_label) = &self.center_label {
+            for (i, label) in center_label.lines().take(2).enumerate() {
+                let row = center_row + i as i32;
+                let hole_width = (0..rows_per_cell)
+                    .map(|sub_row| geometry.hole_width(row * rows_per_cell + sub_row - center_y))
+                    .min()
+                    .unwrap_or(0);
+                let hole_len = (2 * hole_width - 1).max(0) as usize;
+                let start = center_x - hole_width + 1;
 
-                let mut iter = (0..=max_label_idx)
-                    .map(|x| x * 2) // space between labels
-                    .map(|x| x - max_label_idx); // center at y=0
+                let text = utils::center_text(label, hole_len);
+                let padding = text.len() - text.trim_start().len();
+                canvas.text(start as usize + padding, row as usize, text.trim(), None);
+            }
+        }
 
-                if let Some(idx) = iter.position(|i| i == y) {
-                    if let Some(item) = data.get(idx) {
-                        output.push_str(&item.format_label(total));
-                    } else if self.total {
-                        let total : f32 = data.iter().map(|d| d.value).sum();
-                        output.push_str(&format!("Total: {}", total));
-                    }
+        // slices which couldn't be labeled inside are always in the legend or get a callout
+        let unlabeled: Vec<usize> = (0..data.len()).filter(|idx| !labeled[*idx]).collect();
+        if self.callouts {
+            canvas = self.draw_callouts(&canvas, &geometry, data, total, &unlabeled);
+        }
+        let legend: Vec<usize> = match (self.callouts, self.legend) {
+            (true, _) => Vec::new(),
+            (false, true) => (0..data.len()).collect(),
+            (false, false) if self.slice_labels != SliceLabel::None => unlabeled,
+            (false, false) => Vec::new(),
+        };
+        let show_legend = with_legend && !legend.is_empty();
+
+        let entries: Vec<String> = match self.legend_table {
+            true => self.legend_table_rows(data, &legend, &boosted, total),
+            false => {
+                let percentages = self.percentages(data, total);
+                // the values of the entries are only needed by a `legend_format`
+                let legend_entries = match (&self.legend_format, legend.is_empty()) {
+                    (Some(_), false) => LegendEntry::all(data, total, &percentages),
+                    _ => Vec::new(),
+                };
+                let mut entries: Vec<String> = legend
+                    .iter()
+                    .map(|&idx| {
+                        let format = |label: &str| {
+                            let item = Data {
+                                label: label.to_string(),
+                                ..data[idx].clone()
+                            };
+                            let mut entry = match &self.legend_format {
+                                Some(format) => format.format(
+                                    &LegendEntry {
+                                        data: &item,
+                                        ..legend_entries[idx]
+                                    },
+                                    &self.number_format,
+                                    &percentages,
+                                ),
+                                None => {
+                                    item.format_label(percentages.get(idx, 2), &self.number_format)
+                                }
+                            };
+                            if let (true, Some(marker)) = (boosted[idx], &self.boosted_marker) {
+                                entry.push(' ');
+                                entry.push_str(marker);
+                            }
+                            entry
+                        };
+                        match &self.legend_format {
+                            Some(LegendFormat::Closure(_)) => format(&data[idx].label),
+                            _ => self.fit_legend_entry(&data[idx].label, format),
+                        }
+                    })
+                    .collect();
+                if self.total {
+                    entries.push(format!("Total: {}", self.number_format.format(total)));
                 }
+                entries
             }
+        };
 
-            output
-        });
+        let lines = (0..canvas.height()).map(|row| canvas.line(row, true));
+        match self.legend_position {
+            _ if !show_legend => lines.collect(),
+            LegendPosition::Right | LegendPosition::Left => {
+                // the lines of wrapped entries follow each other, entries are separated by an empty row
+                let compact: Vec<&str> =
+                    entries.iter().flat_map(|entry| entry.split('\n')).collect();
+                let spaced: Vec<&str> = entries
+                    .iter()
+                    .flat_map(|entry| iter::once("").chain(entry.split('\n')))
+                    .skip(1)
+                    .collect();
+                let legend_width = compact.iter().map(|line| utils::text_width(line)).max();
+                let legend_width = legend_width.unwrap_or(0);
+                let height = canvas.height() as i32;
 
-        for line in circle {
-            writeln!(&mut f, "{}", line)?;
-        }
+                // the entries are put on every second row around the center. If they don't fit, they are put
+                // on consecutive rows instead and if that isn't enough either, they continue below the pie
+                let fits =
+                    |first: i32, rows: &[&str]| first >= 0 && first + (rows.len() as i32) <= height;
+                let reserved = spaced.len() as i32 + if self.total { 0 } else { 2 };
+                let centered = |rows: &[&str]| center_row - (rows.len() as i32 - 1) / 2;
+                let (first, rows) = match center_row - (reserved - 1) / 2 {
+                    first if !self.legend_table && fits(first, &spaced) => (first, spaced),
+                    _ if fits(centered(&compact), &compact) => (centered(&compact), compact),
+                    _ => (0, compact),
+                };
 
-        Ok(())
+                (0..height.max(first + rows.len() as i32))
+                    .map(|row| {
+                        let entry = match row >= first {
+                            true => rows.get((row - first) as usize).copied(),
+                            false => None,
+                        };
+                        let entry = entry.unwrap_or("");
+                        let line = |trim| match row < height {
+                            true => canvas.line(row as usize, trim),
+                            false if trim => String::new(),
+                            false => " ".repeat(canvas.width()),
+                        };
+
+                        match self.legend_position {
+                            LegendPosition::Left => {
+                                let padding =
+                                    legend_width - utils::text_width(entry) + LABEL_PADDING;
+                                let line =
+                                    format!("{}{}{}", entry, " ".repeat(padding), line(true));
+                                line.trim_end().to_string()
+                            }
+                            _ => format!("{}{}{}", line(false), " ".repeat(LABEL_PADDING), entry),
+                        }
+                    })
+                    .collect()
+            }
+            LegendPosition::Above | LegendPosition::Below => {
+                // the entries are wrapped into as many columns as fit below the pie, the total gets its own line
+                let (items, total) = match self.legend_table {
+                    true => entries.split_at(0),
+                    false => entries.split_at(legend.len()),
+                };
+                let items: Vec<Vec<&str>> = items
+                    .iter()
+                    .map(|entry| entry.split('\n').collect())
+                    .collect();
+                let column_width = items
+                    .iter()
+                    .flatten()
+                    .map(|line| utils::text_width(line))
+                    .max();
+                let column_width = column_width.unwrap_or(0) + LABEL_PADDING;
+                let columns = ((canvas.width() + LABEL_PADDING) / column_width).max(1);
+                let rows = items.chunks(columns).flat_map(|entries| {
+                    let height = entries.iter().map(Vec::len).max().unwrap_or(0);
+                    (0..height).map(move |row| {
+                        let line: String = entries
+                            .iter()
+                            .map(|entry| {
+                                let entry = entry.get(row).copied().unwrap_or("");
+                                let padding = column_width - utils::text_width(entry);
+                                format!("{}{}", entry, " ".repeat(padding))
+                            })
+                            .collect();
+                        line.trim_end().to_string()
+                    })
+                });
+                let legend = rows.chain(total.iter().cloned());
+
+                match self.legend_position {
+                    LegendPosition::Above => legend.chain(lines).collect(),
+                    _ => lines.chain(legend).collect(),
+                }
+            }
+        }
     }
 }
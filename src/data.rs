@@ -1,3 +1,4 @@
+use crate::number::NumberFormat;
 use crate::Style;
 
 /// A data item is used to describe a datapoint that will be rendered in the [`Chart::draw`](struct.Chart.html#method.draw)-method.
@@ -10,6 +11,8 @@ pub struct Data {
     /// If `color` is specified each character will be prefixed with the ANSI escape code for its color.
     pub color: Option<Style>,
     /// The `fill` char controls how the area in the pie chart will be rendered.
+    /// Wide chars like emoji take up two columns. They are only drawn where both columns belong to the slice,
+    /// so the slice is filled with half as many of them.
     pub fill: char,
 }
 impl Default for Data {
@@ -24,7 +27,7 @@ impl Default for Data {
 }
 
 impl Data {
-    pub(crate) fn format_label(&self, total: f32) -> String {
+    pub(crate) fn format_label(&self, percent: f32, numbers: &NumberFormat) -> String {
         let fill = match self.color {
             Some(c) => c.paint(self.fill.to_string()).to_string(),
             None => self.fill.to_string(),
@@ -33,8 +36,13 @@ impl Data {
             "{} {} {:.2}% ({})",
             fill,
             self.label,
-            self.value / total * 100.0,
-            self.value,
+            percent,
+            numbers.format(self.value),
         )
     }
+
+    /// The label and percentage without the fill char, as it is written next to a callout.
+    pub(crate) fn format_callout(&self, percent: f32) -> String {
+        format!("{} {:.2}%", self.label, percent)
+    }
 }
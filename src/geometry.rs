@@ -0,0 +1,359 @@
+use crate::utils;
+
+/// The `Geometry` describes which slice covers which cell, in cell coordinates relative to the center of the pie.
+#[derive(Debug)]
+pub struct Geometry {
+    pub radius: i32,
+    pub aspect_ratio: f32,
+    pub center_x: i32,
+    pub inner_radius: i32,
+    hole_ratio: f32,
+    start_angle: f32,
+    clockwise: bool,
+    angles: Vec<f32>,
+    offsets: Vec<(usize, i32, i32)>,
+}
+
+impl Geometry {
+    pub fn new(
+        radius: i32,
+        aspect_ratio: f32,
+        hole_ratio: f32,
+        start_angle: f32,
+        clockwise: bool,
+        angles: Vec<f32>,
+    ) -> Self {
+        Geometry {
+            radius,
+            aspect_ratio,
+            center_x: utils::calculate_center_x(radius, aspect_ratio),
+            inner_radius: (radius as f32 * hole_ratio).round() as i32,
+            hole_ratio,
+            start_angle,
+            clockwise,
+            angles,
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Moves the slice at `idx` outwards along its bisector by `offset` rows.
+    pub fn explode(&mut self, idx: usize, offset: f32) {
+        let start = idx.checked_sub(1).map_or(0.0, |i| self.angles[i]);
+        let (dx, dy) = self.point_at((start + self.angles[idx]) / 2.0, offset);
+        self.offsets
+            .push((idx, dx.round() as i32, dy.round() as i32));
+    }
+
+    /// The amount of columns and rows the exploded slices extend beyond the pie in each direction.
+    fn padding(&self) -> (i32, i32) {
+        self.offsets.iter().fold((0, 0), |(x, y), (_, dx, dy)| {
+            (x.max(dx.abs()), y.max(dy.abs()))
+        })
+    }
+
+    /// The position of the center of the pie on the canvas.
+    pub fn center(&self) -> (i32, i32) {
+        let (padding_x, padding_y) = self.padding();
+        (self.center_x + padding_x, self.radius + padding_y)
+    }
+
+    /// The amount of columns and rows of the canvas the pie is drawn into.
+    pub fn size(&self) -> (usize, usize) {
+        let (center_x, center_y) = self.center();
+        (2 * center_x as usize + 1, 2 * center_y as usize + 1)
+    }
+
+    /// The width of the hole at height `y`, cells with `x.abs() < hole_width` are inside.
+    pub fn hole_width(&self, y: i32) -> i32 {
+        match y.abs() < self.inner_radius {
+            true => utils::calculate_width(self.inner_radius, y, self.aspect_ratio),
+            false => 0,
+        }
+    }
+
+    /// Returns the index of the slice covering the center of the cell at (`x`, `y`), if any.
+    pub fn slice_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.find_slice(x as f32, y as f32, |x, y| {
+            let (x, y) = (x as i32, y as i32);
+            self.in_ellipse(x, y) && x.abs() >= self.hole_width(y)
+        })
+    }
+
+    /// Returns whether the center of the cell at (`x`, `y`) is inside the outer ellipse of the pie.
+    fn in_ellipse(&self, x: i32, y: i32) -> bool {
+        y.abs() <= self.radius
+            && x.abs() <= utils::calculate_width(self.radius, y, self.aspect_ratio)
+    }
+
+    /// If the cell at (`x`, `y`) is on the outer edge of the ellipse, returns which of its
+    /// neighbours (above, below, left, right) are outside of it.
+    pub fn outline_at(&self, x: i32, y: i32) -> Option<[bool; 4]> {
+        if !self.in_ellipse(x, y) {
+            return None;
+        }
+        let sides =
+            [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)].map(|(x, y)| !self.in_ellipse(x, y));
+        match sides.contains(&true) {
+            true => Some(sides),
+            false => None,
+        }
+    }
+
+    /// Returns the direction of the ellipse's tangent at the cell (`x`, `y`).
+    pub fn tangent_at(&self, x: i32, y: i32) -> (f32, f32) {
+        (-y as f32, x as f32 / self.aspect_ratio)
+    }
+
+    /// Returns the index of the slice covering the exact point at (`x`, `y`), if any.
+    /// Unlike [`slice_at`](#method.slice_at), the edge of the pie is the exact ellipse around the outermost cells.
+    pub fn sample_at(&self, x: f32, y: f32) -> Option<usize> {
+        let radius = self.radius as f32 + 0.5;
+        self.find_slice(x, y, |x, y| {
+            let x = x / self.aspect_ratio.sqrt();
+            let distance = (x * x + y * y).sqrt();
+            distance <= radius && distance >= radius * self.hole_ratio
+        })
+    }
+
+    /// Samples `cols * rows` evenly spaced points of the cell at (`x`, `y`), row by row.
+    pub fn samples_at(&self, x: i32, y: i32, cols: u16, rows: u16) -> Vec<Option<usize>> {
+        (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (col, row)))
+            .map(|(col, row)| {
+                let sample_x = x as f32 + (col as f32 + 0.5) / cols as f32 - 0.5;
+                let sample_y = y as f32 + (row as f32 + 0.5) / rows as f32 - 0.5;
+                self.sample_at(sample_x, sample_y)
+            })
+            .collect()
+    }
+
+    /// Samples `samples * samples` evenly spaced points of the cell at (`x`, `y`).
+    pub fn coverage_at(&self, x: i32, y: i32, samples: u16) -> Coverage {
+        let fraction = 1.0 / (samples as f32).powi(2);
+        let mut coverage = Coverage::default();
+        for idx in self
+            .samples_at(x, y, samples, samples)
+            .into_iter()
+            .flatten()
+        {
+            coverage.add(idx, fraction);
+        }
+        coverage
+    }
+
+    /// Returns how far the slice at `idx` was moved by [`explode`](#method.explode).
+    fn offset(&self, idx: usize) -> (i32, i32) {
+        self.offsets
+            .iter()
+            .find(|(i, _, _)| *i == idx)
+            .map_or((0, 0), |(_, dx, dy)| (*dx, *dy))
+    }
+
+    /// Returns the center of mass of the slice at `idx`.
+    pub fn centroid(&self, idx: usize) -> (f32, f32) {
+        let start = idx.checked_sub(1).map_or(0.0, |i| self.angles[i]);
+        let half_angle = (self.angles[idx] - start).to_radians() / 2.0;
+        let outer = self.radius as f32 + 0.5;
+        let inner = outer * self.hole_ratio;
+
+        // the centroid of a sector of an annulus
+        let distance = match half_angle > 0.0 {
+            true => {
+                let radius =
+                    2.0 / 3.0 * (outer.powi(3) - inner.powi(3)) / (outer.powi(2) - inner.powi(2));
+                radius * half_angle.sin() / half_angle
+            }
+            false => 0.0,
+        };
+        self.bisector_point(idx, distance)
+    }
+
+    /// Returns the point on the bisector of the slice at `idx` with a distance of `distance` rows from the center.
+    pub fn bisector_point(&self, idx: usize, distance: f32) -> (f32, f32) {
+        let start = idx.checked_sub(1).map_or(0.0, |i| self.angles[i]);
+        let (x, y) = self.point_at((start + self.angles[idx]) / 2.0, distance);
+        let (dx, dy) = self.offset(idx);
+        (x + dx as f32, y + dy as f32)
+    }
+
+    /// Returns the angle of the boundary of the slice at `idx` passing through the cell at (`x`, `y`), if any.
+    pub fn boundary_at(&self, x: i32, y: i32, idx: usize) -> Option<f32> {
+        let start = idx.checked_sub(1).map_or(0.0, |i| self.angles[i]);
+        if self.angles[idx] - start >= 360.0 - f32::EPSILON {
+            return None;
+        }
+
+        let (dx, dy) = self.offset(idx);
+        let (x, y) = ((x - dx) as f32, (y - dy) as f32);
+
+        [start, self.angles[idx]].iter().copied().find(|angle| {
+            let (dir_x, dir_y) = self.point_at(*angle, 1.0);
+            // the boundary is a ray from the center, which intersects the square of the cell
+            let distance = (x * dir_y - y * dir_x).abs();
+            x * dir_x + y * dir_y >= 0.0 && distance <= (dir_x.abs() + dir_y.abs()) / 2.0
+        })
+    }
+
+    /// Reassigns cells of `slices` (the slice of each cell, row by row) so that every slice with a non-zero angle
+    /// covers at least `min_cells` cells. The cells closest to the small slice are taken from its largest neighbour.
+    /// Returns which slices received additional cells.
+    pub fn ensure_visible(&self, slices: &mut [Vec<Option<usize>>], min_cells: usize) -> Vec<bool> {
+        let len = self.angles.len();
+        let (center_x, center_y) = self.center();
+        let mut counts = vec![0; len];
+        for idx in slices.iter().flatten().flatten() {
+            counts[*idx] += 1;
+        }
+
+        let mut boosted = vec![false; len];
+        for idx in 0..len {
+            let start = idx.checked_sub(1).map_or(0.0, |i| self.angles[i]);
+            if self.angles[idx] <= start {
+                continue;
+            }
+            let bisector = (start + self.angles[idx]) / 2.0;
+
+            while counts[idx] < min_cells {
+                let can_donate = |i: &usize| *i != idx && counts[*i] > min_cells;
+                let neighbours = [(idx + len - 1) % len, (idx + 1) % len];
+                let donor = neighbours
+                    .iter()
+                    .copied()
+                    .filter(can_donate)
+                    .max_by_key(|i| counts[*i])
+                    .or_else(|| (0..len).filter(can_donate).max_by_key(|i| counts[*i]));
+                let donor = match donor {
+                    Some(donor) => donor,
+                    None => break,
+                };
+
+                let distance = |(col, row): (usize, usize)| {
+                    let x = (col as i32 - center_x) as f32;
+                    let y = (row as i32 - center_y) as f32;
+                    let distance = (self.angle_at(x, y) - bisector).abs();
+                    distance.min(360.0 - distance)
+                };
+                let (col, row) = slices
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(row, line)| {
+                        let cells = line.iter().enumerate();
+                        cells
+                            .filter(|(_, i)| **i == Some(donor))
+                            .map(move |(col, _)| (col, row))
+                    })
+                    .min_by(|a, b| distance(*a).partial_cmp(&distance(*b)).unwrap())
+                    .expect("donor has cells");
+
+                slices[row][col] = Some(idx);
+                counts[idx] += 1;
+                counts[donor] -= 1;
+                boosted[idx] = true;
+            }
+        }
+        boosted
+    }
+
+    fn find_slice(&self, x: f32, y: f32, contains: impl Fn(f32, f32) -> bool) -> Option<usize> {
+        let unexploded_slice_at = |x, y| match contains(x, y) {
+            true => Some(self.slice_at_angle(self.angle_at(x, y))),
+            false => None,
+        };
+
+        // exploded slices are checked first, since they may have moved into cells of their neighbours
+        let exploded = self.offsets.iter().find(|(idx, dx, dy)| {
+            unexploded_slice_at(x - *dx as f32, y - *dy as f32) == Some(*idx)
+        });
+        match exploded {
+            Some((idx, _, _)) => Some(*idx),
+            None => unexploded_slice_at(x, y)
+                .filter(|idx| self.offsets.iter().all(|(i, _, _)| i != idx)),
+        }
+    }
+
+    fn slice_at_angle(&self, angle: f32) -> usize {
+        // rounding errors can make the last angle slightly smaller than 360°
+        let idx = self.angles.iter().position(|a| angle <= *a);
+        idx.unwrap_or(self.angles.len() - 1)
+    }
+
+    /// Returns the angle of the point at (`x`, `y`), measured from the start of the first slice.
+    fn angle_at(&self, x: f32, y: f32) -> f32 {
+        // undo the horizontal stretch, so that the angle is the one on the unstretched circle
+        // and the area of a slice is proportional to its share. 0° is at the top, going clockwise
+        let x = x / self.aspect_ratio.sqrt();
+        let angle = 360.0 / 2.0 - x.atan2(y).to_degrees();
+        match self.clockwise {
+            true => (angle - self.start_angle).rem_euclid(360.0),
+            false => (self.start_angle - angle).rem_euclid(360.0),
+        }
+    }
+
+    /// Returns the offset from the center of the point at `angle` degrees with a distance of `distance` rows.
+    pub fn point_at(&self, angle: f32, distance: f32) -> (f32, f32) {
+        let angle = match self.clockwise {
+            true => self.start_angle + angle,
+            false => self.start_angle - angle,
+        };
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let x = sin * self.aspect_ratio.sqrt();
+
+        (x * distance, -cos * distance)
+    }
+}
+
+/// The share of a cell covered by each slice, as returned by [`Chart::coverage`](struct.Chart.html#method.coverage).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    slices: Vec<(usize, f32)>,
+}
+
+impl Coverage {
+    /// Combines the coverage of the `parts` of a cell, which are sampled separately.
+    pub(crate) fn combine<'a>(parts: impl Iterator<Item = &'a Coverage>, count: usize) -> Self {
+        let mut coverage = Coverage::default();
+        for part in parts {
+            for &(idx, fraction) in &part.slices {
+                coverage.add(idx, fraction / count as f32);
+            }
+        }
+        coverage
+    }
+
+    fn add(&mut self, idx: usize, fraction: f32) {
+        match self.slices.iter_mut().find(|(i, _)| *i == idx) {
+            Some((_, f)) => *f += fraction,
+            None => self.slices.push((idx, fraction)),
+        }
+    }
+
+    /// Returns the fraction of the cell covered by the slice at `index`, between `0.0` and `1.0`.
+    pub fn fraction(&self, index: usize) -> f32 {
+        self.slices
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map_or(0.0, |(_, f)| *f)
+    }
+
+    /// Iterates over the indices of the slices covering the cell together with their fractions.
+    pub fn slices(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+        self.slices.iter().copied()
+    }
+
+    /// Returns the fraction of the cell which isn't covered by any slice.
+    pub fn empty(&self) -> f32 {
+        1.0 - self.slices.iter().map(|(_, f)| f).sum::<f32>()
+    }
+
+    /// Returns the slice covering most of the cell, or `None` if most of the cell is empty.
+    pub fn dominant(&self) -> Option<usize> {
+        let &(idx, fraction) = self
+            .slices
+            .iter()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).expect("fractions are never NaN"))?;
+        match fraction > self.empty() {
+            true => Some(idx),
+            false => None,
+        }
+    }
+}
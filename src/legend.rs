@@ -0,0 +1,262 @@
+use crate::number::{NumberFormat, Percentages};
+use crate::{utils, Data};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The values of a legend entry, which are passed to a [`Chart::legend_formatter`](struct.Chart.html#method.legend_formatter).
+#[derive(Debug, Clone, Copy)]
+pub struct LegendEntry<'a> {
+    /// The data item of the entry.
+    pub data: &'a Data,
+    /// The index of the data item.
+    pub index: usize,
+    /// The share of the item in the total, in percent.
+    pub percent: f32,
+    /// The sum of the percentages of this and all previous items.
+    pub cumulative_percent: f32,
+    /// The position of the item when sorted by value, starting at `1` for the largest one.
+    pub rank: usize,
+    /// The sum of all values.
+    pub total: f32,
+}
+
+impl<'a> LegendEntry<'a> {
+    /// Computes the entries of all items of `data`, with percentages rounded to two decimal places.
+    pub(crate) fn all(data: &'a [Data], total: f32, percentages: &Percentages<'_>) -> Vec<Self> {
+        // items with the same value are ranked by their index
+        let mut by_value: Vec<usize> = (0..data.len()).collect();
+        by_value.sort_by(|&a, &b| {
+            let order = data[b].value.partial_cmp(&data[a].value);
+            order.unwrap_or(Ordering::Equal).then(a.cmp(&b))
+        });
+        let mut ranks = vec![0; data.len()];
+        for (rank, &index) in by_value.iter().enumerate() {
+            ranks[index] = rank + 1;
+        }
+
+        data.iter()
+            .enumerate()
+            .map(|(index, item)| LegendEntry {
+                data: item,
+                index,
+                percent: percentages.get(index, 2),
+                cumulative_percent: percentages.cumulative(index, 2),
+                rank: ranks[index],
+                total,
+            })
+            .collect()
+    }
+}
+
+/// How the legend entries are formatted, see [`Chart::legend_format`](struct.Chart.html#method.legend_format).
+pub(crate) enum LegendFormat {
+    Template(Vec<Segment>),
+    Closure(Box<dyn Fn(&LegendEntry<'_>) -> String + Send + Sync>),
+}
+
+impl fmt::Debug for LegendFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LegendFormat::Template(segments) => f.debug_tuple("Template").field(segments).finish(),
+            LegendFormat::Closure(_) => f.debug_tuple("Closure").finish(),
+        }
+    }
+}
+
+impl LegendFormat {
+    /// Parses a template like `{fill} {label:<12} {percent:>6.1}%`, panics if it is invalid.
+    pub fn template(template: &str) -> Self {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' | '}' if chars.peek() == Some(&c) => {
+                    chars.next();
+                    text.push(c);
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => panic!("unclosed `{}` in legend format", "{"),
+                        }
+                    }
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                    segments.push(Segment::parse(&placeholder));
+                }
+                '}' => panic!("unmatched `{}` in legend format", "}"),
+                c => text.push(c),
+            }
+        }
+        segments.push(Segment::Text(text));
+        LegendFormat::Template(segments)
+    }
+
+    pub fn format(
+        &self,
+        entry: &LegendEntry<'_>,
+        numbers: &NumberFormat,
+        percentages: &Percentages<'_>,
+    ) -> String {
+        match self {
+            LegendFormat::Template(segments) => segments
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Text(text) => text.clone(),
+                    Segment::Placeholder(field, spec) => {
+                        spec.format(*field, entry, numbers, percentages)
+                    }
+                })
+                .collect(),
+            LegendFormat::Closure(closure) => closure(entry),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Segment {
+    Text(String),
+    Placeholder(Field, Spec),
+}
+
+impl Segment {
+    /// Parses the inside of a placeholder, e.g. `percent:>6.1`.
+    fn parse(placeholder: &str) -> Self {
+        let (name, spec) = match placeholder.find(':') {
+            Some(i) => (&placeholder[..i], &placeholder[i + 1..]),
+            None => (placeholder, ""),
+        };
+        let field = match name {
+            "fill" => Field::Fill,
+            "label" => Field::Label,
+            "value" => Field::Value,
+            "percent" => Field::Percent,
+            "cumulative" => Field::Cumulative,
+            "rank" => Field::Rank,
+            _ => panic!("unknown placeholder `{{{}}}` in legend format", name),
+        };
+        Segment::Placeholder(field, Spec::parse(spec))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Field {
+    Fill,
+    Label,
+    Value,
+    Percent,
+    Cumulative,
+    Rank,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// The format spec of a placeholder: `[[fill]align][width][.precision]`, like the one of `format!`.
+#[derive(Debug, Default)]
+pub(crate) struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Self {
+        let align = |c| match c {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        };
+        let mut result = Spec::default();
+        let mut chars: Vec<char> = spec.chars().collect();
+        match chars.as_slice() {
+            [fill, c, ..] if align(*c).is_some() => {
+                result.fill = Some(*fill);
+                result.align = align(*c);
+                chars.drain(..2);
+            }
+            [c, ..] if align(*c).is_some() => {
+                result.align = align(*c);
+                chars.remove(0);
+            }
+            _ => {}
+        }
+
+        let rest: String = chars.into_iter().collect();
+        let (width, precision) = match rest.find('.') {
+            Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+            None => (rest.as_str(), None),
+        };
+        let number = |s: &str| {
+            s.parse()
+                .unwrap_or_else(|_| panic!("invalid format spec `{}` in legend format", spec))
+        };
+        if !width.is_empty() {
+            result.width = number(width);
+        }
+        result.precision = precision.map(number);
+        result
+    }
+
+    fn format(
+        &self,
+        field: Field,
+        entry: &LegendEntry<'_>,
+        numbers: &NumberFormat,
+        percentages: &Percentages<'_>,
+    ) -> String {
+        let precision = self.precision.unwrap_or(2);
+        let data = entry.data;
+        let text = match field {
+            Field::Fill => match data.color {
+                Some(c) => c.paint(data.fill.to_string()).to_string(),
+                None => data.fill.to_string(),
+            },
+            Field::Label => match self.precision {
+                Some(precision) => data.label.chars().take(precision).collect(),
+                None => data.label.clone(),
+            },
+            Field::Value => match self.precision {
+                Some(precision) => NumberFormat {
+                    precision: Some(precision),
+                    ..numbers.clone()
+                }
+                .format(data.value),
+                None => numbers.format(data.value),
+            },
+            Field::Percent => {
+                let percent = percentages.get(entry.index, precision);
+                format!("{:.*}", precision, percent)
+            }
+            Field::Cumulative => {
+                let percent = percentages.cumulative(entry.index, precision);
+                format!("{:.*}", precision, percent)
+            }
+            Field::Rank => entry.rank.to_string(),
+        };
+
+        // text is aligned to the left and numbers to the right by default, like `format!` does
+        let align = match (self.align, field) {
+            (Some(align), _) => align,
+            (None, Field::Fill) | (None, Field::Label) => Align::Left,
+            (None, _) => Align::Right,
+        };
+        let padding = self.width.saturating_sub(utils::text_width(&text));
+        let (before, after) = match align {
+            Align::Left => (0, padding),
+            Align::Center => (padding / 2, padding - padding / 2),
+            Align::Right => (padding, 0),
+        };
+        let fill = self.fill.unwrap_or(' ').to_string();
+        format!("{}{}{}", fill.repeat(before), text, fill.repeat(after))
+    }
+}
@@ -12,16 +12,29 @@
 //!
 //! For more information visit the docs for [`Chart`](struct.Chart.html).
 
+mod canvas;
 mod chart;
 mod data;
+mod geometry;
+mod legend;
+mod number;
+mod terminal;
 mod utils;
 pub use ansi_term::{Color, Style};
-pub use chart::Chart;
+pub use chart::{
+    Chart, Dimensions, EdgeStyle, LabelOverflow, LegendPosition, Outline, RenderMode, SliceLabel,
+};
 pub use data::Data;
+pub use geometry::Coverage;
+pub use legend::LegendEntry;
+pub use number::UnitPrefix;
 
 #[cfg(test)]
 mod tests {
-    use crate::{Chart, Data};
+    use crate::{
+        Chart, Data, EdgeStyle, LabelOverflow, LegendPosition, Outline, RenderMode, SliceLabel,
+        UnitPrefix,
+    };
     use std::io;
 
     struct NullWriter;
@@ -67,6 +80,17 @@ mod tests {
         chart.draw_into(NullWriter, &data).unwrap();
     }
 
+    #[test]
+    fn very_much_data_exact_percentages() {
+        let data = vec![Default::default(); 2048];
+        let mut chart = Chart::new();
+        chart
+            .exact_percentages(true)
+            .draw_into(NullWriter, &data)
+            .unwrap();
+        chart.legend(true).draw_into(NullWriter, &data).unwrap();
+    }
+
     #[test]
     fn config_combinations() {
         let mut chart = Chart::new();
@@ -85,6 +109,793 @@ mod tests {
         }
     }
 
+    #[test]
+    fn donut_hole() {
+        let mut output = Vec::new();
+        Chart::new()
+            .hole_ratio(0.5)
+            .draw_into(&mut output, &[Default::default()])
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+
+        assert!(!lines[0].trim().contains(' '));
+        assert!(lines[lines.len() / 2].trim().contains("   "));
+    }
+
+    #[test]
+    fn donut_hole_ratios() {
+        let mut chart = Chart::new();
+        for radius in 0..=5 {
+            chart.radius(radius);
+            for hole_ratio in &[0.0, 0.2, 0.5, 0.9] {
+                chart.hole_ratio(*hole_ratio);
+                for data_len in 1..=5 {
+                    let data = vec![Default::default(); data_len];
+                    chart.draw_into(NullWriter, &data).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn donut_center_label() {
+        let mut output = Vec::new();
+        Chart::new()
+            .hole_ratio(0.5)
+            .center_label("Total\na very long second line")
+            .draw_into(&mut output, &[Default::default()])
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+
+        assert!(lines[lines.len() / 2].contains(" Total "));
+        assert!(lines[lines.len() / 2 + 1].contains("a very long …"));
+    }
+
+    #[test]
+    fn exploded_slices() {
+        #[rustfmt::skip]
+        let data = [
+            Data { fill: 'a', ..Default::default() },
+            Data { fill: 'b', ..Default::default() },
+            Data { fill: 'c', ..Default::default() },
+        ];
+        let mut output = Vec::new();
+        Chart::new()
+            .radius(8)
+            .explode(0, 2)
+            .explode(7, 2)
+            .draw_into(&mut output, &data)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.lines().count() > 17);
+        assert!(data.iter().all(|d| output.contains(d.fill)));
+    }
+
+    #[test]
+    fn start_angle_and_direction() {
+        #[rustfmt::skip]
+        let data = [
+            Data { fill: 'a', ..Default::default() },
+            Data { fill: 'b', ..Default::default() },
+        ];
+        let draw = |chart: &Chart| {
+            let mut output = Vec::new();
+            chart.draw_into(&mut output, &data).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            let first = output.lines().next().unwrap().trim().to_string();
+            let middle = output.lines().nth(8).unwrap().trim().to_string();
+            (first, middle)
+        };
+
+        let (first, middle) = draw(&Chart::new());
+        assert!(middle.starts_with('b') && middle.ends_with('a'));
+        assert!(first.contains('a') && first.contains('b'));
+
+        let (_, middle) = draw(Chart::new().clockwise(false));
+        assert!(middle.starts_with('a') && middle.ends_with('b'));
+
+        let (first, _) = draw(Chart::new().start_angle(90.0));
+        assert!(first.chars().all(|c| c == 'b'));
+    }
+
+    #[test]
+    fn slice_area_proportional_to_value() {
+        #[rustfmt::skip]
+        let data: Vec<_> = ['a', 'b', 'c', 'd'].iter().zip(1..)
+            .map(|(&fill, value)| Data { fill, value: value as f32, ..Default::default() })
+            .collect();
+        let total: f32 = data.iter().map(|d| d.value).sum();
+
+        let mut chart = Chart::new();
+        for radius in 6..=12 {
+            chart.radius(radius);
+            for &aspect_ratio in &[1.0, 2.0, 2.4, 3.0, 3.7, 4.0] {
+                chart.aspect_ratio(aspect_ratio);
+                for &start_angle in &[0.0, 45.0] {
+                    chart.start_angle(start_angle);
+
+                    let mut output = Vec::new();
+                    chart.draw_into(&mut output, &data).unwrap();
+                    let output = String::from_utf8(output).unwrap();
+
+                    let cells = output.chars().filter(|c| c.is_alphabetic()).count() as f32;
+                    for item in &data {
+                        let count = output.chars().filter(|c| *c == item.fill).count() as f32;
+                        let error = count / cells - item.value / total;
+                        assert!(
+                            error.abs() < 0.04,
+                            "radius {}, aspect ratio {}: '{}' is off by {}",
+                            radius,
+                            aspect_ratio,
+                            item.fill,
+                            error
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn supersampling_coverage() {
+        #[rustfmt::skip]
+        let data: Vec<_> = [1.0, 2.0, 0.1].iter()
+            .map(|&value| Data { value, ..Default::default() })
+            .collect();
+        let total: f32 = data.iter().map(|d| d.value).sum();
+
+        let mut chart = Chart::new();
+        chart.radius(8).supersampling(4);
+        chart.draw_into(NullWriter, &data).unwrap();
+
+        let coverage: Vec<_> = chart.coverage(&data).into_iter().flatten().collect();
+        assert!(coverage.iter().all(|c| (0.0..=1.0).contains(&c.empty())));
+
+        let covered: f32 = coverage.iter().map(|c| 1.0 - c.empty()).sum();
+        for (idx, item) in data.iter().enumerate() {
+            let area: f32 = coverage.iter().map(|c| c.fraction(idx)).sum();
+            assert!((area / covered - item.value / total).abs() < 0.01);
+        }
+
+        // the coverage has one entry per cell which is drawn
+        chart.render_mode(RenderMode::HalfBlock);
+        let mut output = Vec::new();
+        chart.draw_into(&mut output, &data).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let coverage = chart.coverage(&data);
+        assert_eq!(coverage.len(), output.lines().count());
+        assert!(coverage
+            .iter()
+            .flatten()
+            .all(|c| (0.0..=1.0).contains(&c.empty())));
+    }
+
+    #[test]
+    fn small_slices_visible() {
+        #[rustfmt::skip]
+        let data = [
+            Data { fill: 'a', value: 100.0, ..Default::default() },
+            Data { fill: 'b', value: 0.5, label: "b".into(), ..Default::default() },
+            Data { fill: 'c', value: 0.0, ..Default::default() },
+            Data { fill: 'd', value: 0.5, ..Default::default() },
+        ];
+        let draw = |chart: &Chart| {
+            let mut output = Vec::new();
+            chart.draw_into(&mut output, &data).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let mut chart = Chart::new();
+        chart.radius(3);
+        let output = draw(&chart);
+        assert!(!output.contains('b') && !output.contains('d'));
+
+        chart.min_slice_cells(2).boosted_marker("*");
+        let output = draw(&chart);
+        assert_eq!(output.matches('b').count(), 2);
+        assert_eq!(output.matches('d').count(), 2);
+        assert!(!output.contains('c'));
+
+        let output = draw(chart.legend(true));
+        assert!(output.contains("b 0.50% (0.5) *"));
+
+        let data = vec![Default::default(); 2048];
+        chart
+            .min_slice_cells(1)
+            .draw_into(NullWriter, &data)
+            .unwrap();
+    }
+
+    #[test]
+    fn braille_mode() {
+        assert_eq!(crate::utils::braille(&[true; 8]), '⣿');
+        assert_eq!(crate::utils::braille(&[false; 8]), '⠀');
+        #[rustfmt::skip]
+        assert_eq!(crate::utils::braille(&[true, false, false, false, false, false, false, true]), '⢁');
+
+        let data = vec![Default::default(); 3];
+        let mut output = Vec::new();
+        Chart::new()
+            .radius(3)
+            .render_mode(RenderMode::Braille)
+            .draw_into(&mut output, &data)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let braille = |c: char| ('\u{2800}'..='\u{28ff}').contains(&c);
+        assert!(output.chars().all(|c| braille(c) || c == ' ' || c == '\n'));
+        assert!(output.contains('⣿'));
+    }
+
+    #[test]
+    fn half_block_mode() {
+        let data = vec![Default::default(); 3];
+        let mut output = Vec::new();
+        Chart::new()
+            .radius(4)
+            .aspect_ratio(1)
+            .render_mode(RenderMode::HalfBlock)
+            .draw_into(&mut output, &data)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.chars().all(|c| "▀▄█ \n".contains(c)));
+        assert_eq!(output.lines().count(), 9);
+        assert_eq!(output.lines().map(|l| l.chars().count()).max(), Some(17));
+    }
+
+    #[test]
+    fn edge_styles() {
+        assert_eq!(
+            crate::utils::quadrant_block([true, false, false, true]),
+            '▚'
+        );
+        assert_eq!(crate::utils::quadrant_block([false, true, true, true]), '▟');
+
+        #[rustfmt::skip]
+        let data = [
+            Data { fill: 'a', ..Default::default() },
+            Data { fill: 'b', ..Default::default() },
+        ];
+        let draw = |chart: &Chart| {
+            let mut output = Vec::new();
+            chart.draw_into(&mut output, &data).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let mut chart = Chart::new();
+
+        let output = draw(chart.edge_style(EdgeStyle::Quadrants));
+        assert!(output.contains('a') && output.contains('b'));
+        assert!(output.chars().any(|c| "▘▝▖▗▌▐▀▄▛▜▙▟".contains(c)));
+
+        let output = draw(chart.edge_style(EdgeStyle::Shade));
+        assert!(output.contains('a') && output.contains('b'));
+        assert!(output.chars().any(|c| "░▒▓".contains(c)));
+    }
+
+    #[test]
+    fn separators() {
+        let draw = |data: &[Data]| {
+            let mut output = Vec::new();
+            let mut chart = Chart::new();
+            chart.separators(true).draw_into(&mut output, data).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let output = draw(&vec![Default::default(); 2]);
+        let first_line = output.lines().next().unwrap();
+        assert!(first_line.contains('│'));
+        assert!(output.lines().all(|line| line.contains('│')));
+
+        let output = draw(&vec![Default::default(); 4]);
+        assert!(output.contains('─') && output.contains('│'));
+
+        let output = draw(&[Default::default()]);
+        assert!(!output.contains('│'));
+    }
+
+    #[test]
+    fn outline() {
+        let data = vec![Default::default(); 3];
+        let draw = |chart: &Chart| {
+            let mut output = Vec::new();
+            chart.draw_into(&mut output, &data).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let mut chart = Chart::new();
+        chart.legend(true);
+        let plain = draw(&chart);
+
+        for &outline in &[Outline::Lines, Outline::Braille] {
+            let output = draw(chart.outline(outline));
+            assert_eq!(output.lines().count(), plain.lines().count());
+            for (line, plain) in output.lines().zip(plain.lines()) {
+                assert_eq!(line.chars().count(), plain.chars().count());
+                assert!(!line.starts_with('•') && !line.trim_end().ends_with('•'));
+            }
+        }
+    }
+
+    #[test]
+    fn slice_labels() {
+        #[rustfmt::skip]
+        let data = [
+            Data { label: "big".into(), value: 6.0, ..Default::default() },
+            Data { label: "medium".into(), value: 3.9, ..Default::default() },
+            Data { label: "small".into(), value: 0.1, ..Default::default() },
+        ];
+        let mut output = Vec::new();
+        Chart::new()
+            .slice_labels(SliceLabel::Percent)
+            .draw_into(&mut output, &data)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("60%") && output.contains("39%"));
+        assert!(!output.contains("big") && !output.contains("medium"));
+        assert!(output.contains("small 1.00% (0.1)"));
+    }
+
+    #[test]
+    fn callouts() {
+        let data: Vec<Data> = (0..20)
+            .map(|i| Data {
+                label: format!("item {}", i),
+                ..Default::default()
+            })
+            .collect();
+        let mut output = Vec::new();
+        Chart::new()
+            .radius(3)
+            .callouts(true)
+            .legend(true)
+            .draw_into(&mut output, &data)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        // every label gets its own line on either side of the pie instead of the legend
+        for item in &data {
+            let label = format!("• {} 5.00%", item.label);
+            assert_eq!(output.matches(&label).count(), 1);
+        }
+        assert!(output.lines().all(|line| line.matches('%').count() <= 2));
+        assert!(output.contains('─') && !output.contains("(1)"));
+    }
+
+    #[test]
+    fn legend_positions() {
+        #[rustfmt::skip]
+        let data = [
+            Data { label: "a".into(), value: 1.0, fill: '*', ..Default::default() },
+            Data { label: "b".into(), value: 1.0, fill: '+', ..Default::default() },
+            Data { label: "c".into(), value: 2.0, fill: '#', ..Default::default() },
+        ];
+        let draw = |position| {
+            let mut output = Vec::new();
+            Chart::new()
+                .radius(10)
+                .legend(true)
+                .total(true)
+                .legend_position(position)
+                .draw_into(&mut output, &data)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let left = draw(LegendPosition::Left);
+        let line = left.lines().find(|line| line.contains("a 25.00%")).unwrap();
+        assert!(line.starts_with("* a 25.00% (1)") && line.ends_with('*'));
+
+        // short entries are wrapped into multiple columns
+        let above = draw(LegendPosition::Above);
+        let lines: Vec<&str> = above.lines().collect();
+        assert_eq!(lines[0], "* a 25.00% (1)  + b 25.00% (1)");
+        assert_eq!(lines[1], "# c 50.00% (2)");
+        assert_eq!(lines[2], "Total: 4");
+
+        let below = draw(LegendPosition::Below);
+        let lines: Vec<&str> = below.lines().rev().take(3).collect();
+        assert_eq!(
+            lines,
+            [
+                "Total: 4",
+                "# c 50.00% (2)",
+                "* a 25.00% (1)  + b 25.00% (1)"
+            ]
+        );
+        assert_eq!(above.lines().count(), below.lines().count());
+    }
+
+    #[test]
+    fn legend_never_drops_entries() {
+        let positions = [
+            LegendPosition::Right,
+            LegendPosition::Left,
+            LegendPosition::Above,
+            LegendPosition::Below,
+        ];
+        for len in 1..=64 {
+            let data: Vec<Data> = (0..len)
+                .map(|i| Data {
+                    label: format!("item {}", i),
+                    ..Default::default()
+                })
+                .collect();
+            for &radius in &[0, 2, 8] {
+                for &position in &positions {
+                    let mut output = Vec::new();
+                    Chart::new()
+                        .radius(radius)
+                        .legend(true)
+                        .total(true)
+                        .legend_position(position)
+                        .draw_into(&mut output, &data)
+                        .unwrap();
+                    let output = String::from_utf8(output).unwrap();
+
+                    for item in &data {
+                        assert!(output.contains(&format!("• {} ", item.label)));
+                    }
+                    assert!(output.contains(&format!("Total: {}", len)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn legend_format() {
+        #[rustfmt::skip]
+        let data = [
+            Data { label: "small".into(), value: 1.0, fill: '*', ..Default::default() },
+            Data { label: "large".into(), value: 3.0, fill: '+', ..Default::default() },
+        ];
+        let draw = |chart: &mut Chart| {
+            let mut output = Vec::new();
+            chart.legend(true).draw_into(&mut output, &data).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let output = draw(Chart::new().legend_format(
+            "{rank}. {fill} {label:.<8}{percent:>6.1}% {value:.1} {cumulative:.0}% {{x}}",
+        ));
+        assert!(output.contains("2. * small...  25.0% 1.0 25% {x}"));
+        assert!(output.contains("1. + large...  75.0% 3.0 100% {x}"));
+
+        let output = draw(Chart::new().legend_formatter(|entry| {
+            format!("{}/{}", entry.data.label.to_uppercase(), entry.total)
+        }));
+        assert!(output.contains("SMALL/4") && output.contains("LARGE/4"));
+    }
+
+    #[test]
+    fn legend_table() {
+        #[rustfmt::skip]
+        let data = [
+            Data { label: "a".into(), value: 1.0, fill: '*', ..Default::default() },
+            Data { label: "longer".into(), value: 10.5, fill: '+', ..Default::default() },
+            Data { label: "ü".into(), value: 100.0, fill: '#', ..Default::default() },
+        ];
+        let mut output = Vec::new();
+        Chart::new()
+            .legend(true)
+            .total(true)
+            .legend_table(true)
+            .legend_header(true)
+            .legend_position(LegendPosition::Below)
+            .draw_into(&mut output, &data)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().rev().take(6).collect();
+
+        assert_eq!(
+            lines,
+            [
+                "  Total   100.00%  111.5",
+                "────────────────────────",
+                "# ü        89.69%    100",
+                "+ longer    9.42%   10.5",
+                "* a         0.90%      1",
+                "  Label   Percent  Value",
+            ]
+        );
+    }
+
+    #[test]
+    fn number_format() {
+        #[rustfmt::skip]
+        let data = [
+            Data { label: "a".into(), value: 1234567.0, ..Default::default() },
+            Data { label: "b".into(), value: 2.5, ..Default::default() },
+            Data { label: "c".into(), value: 0.5, ..Default::default() },
+        ];
+        let draw = |chart: &mut Chart| {
+            let mut output = Vec::new();
+            chart
+                .legend(true)
+                .total(true)
+                .draw_into(&mut output, &data)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let output = draw(
+            Chart::new()
+                .precision(2)
+                .thousands_separator(',')
+                .unit(" ms"),
+        );
+        assert!(output.contains("(1,234,567.00 ms)") && output.contains("(2.50 ms)"));
+        assert!(output.contains("Total: 1,234,570.00 ms"));
+
+        let output = draw(Chart::new().precision(1).unit_prefix(UnitPrefix::Si));
+        assert!(output.contains("(1.2M)") && output.contains("(2.5)"));
+
+        let output = draw(Chart::new().unit_prefix(UnitPrefix::Binary).unit("B"));
+        assert!(output.contains("(1.1773748MiB)") && output.contains("(0.5B)"));
+
+        let output = draw(
+            Chart::new()
+                .precision(0)
+                .legend_format("{value} {value:.1}"),
+        );
+        assert!(output.contains("1234567 1234567.0"));
+    }
+
+    #[test]
+    fn exact_percentages() {
+        let data = vec![Data::default(); 3];
+        let draw = |chart: &mut Chart| {
+            let mut output = Vec::new();
+            chart.legend(true).draw_into(&mut output, &data).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let output = draw(&mut Chart::new());
+        assert_eq!(output.matches("33.33%").count(), 3);
+
+        let output = draw(Chart::new().exact_percentages(true));
+        assert_eq!(output.matches("33.34%").count(), 1);
+        assert_eq!(output.matches("33.33%").count(), 2);
+
+        let output = draw(
+            Chart::new()
+                .exact_percentages(true)
+                .legend_format("{percent:.1} {cumulative:.0}"),
+        );
+        assert!(output.contains("33.4 34") && output.contains("33.3 100"));
+    }
+
+    #[test]
+    fn display_width() {
+        use unicode_width::UnicodeWidthStr;
+
+        #[rustfmt::skip]
+        let data = [
+            Data { label: "チョコ".into(), value: 1.0, fill: '🍫', ..Default::default() },
+            Data { label: "Cafe\u{301}".into(), value: 1.0, fill: '*', ..Default::default() },
+            Data { label: "Vanilla".into(), value: 1.0, fill: '+', ..Default::default() },
+        ];
+        let mut output = Vec::new();
+        Chart::new()
+            .legend(true)
+            .legend_table(true)
+            .legend_position(LegendPosition::Left)
+            .draw_into(&mut output, &data)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        // the columns of the legend and the pie next to it are aligned in the terminal
+        let entries: Vec<&str> = output.lines().filter(|line| line.contains('%')).collect();
+        assert_eq!(entries.len(), 3);
+        for entry in entries {
+            let percent = entry.find('%').unwrap();
+            assert_eq!(entry[..percent].width(), "🍫 チョコ   33.33".width());
+        }
+        let mut single = Vec::new();
+        Chart::new().draw_into(&mut single, &data[..1]).unwrap();
+        let mut narrow = Vec::new();
+        Chart::new().draw_into(&mut narrow, &data[1..2]).unwrap();
+        let single = String::from_utf8(single).unwrap();
+        let narrow = String::from_utf8(narrow).unwrap();
+        for (wide, narrow) in single.lines().zip(narrow.lines()) {
+            assert_eq!(wide.width(), narrow.width());
+        }
+
+        // wide fills never stick out of the pie, so the legend next to it stays aligned
+        let data = vec![
+            Data {
+                fill: '🍫',
+                ..Default::default()
+            };
+            4
+        ];
+        for radius in 1..=8 {
+            let mut output = Vec::new();
+            Chart::new()
+                .radius(radius)
+                .legend(true)
+                .draw_into(&mut output, &data)
+                .unwrap();
+            let output = String::from_utf8(output).unwrap();
+            let columns: Vec<usize> = output
+                .lines()
+                .filter_map(|line| Some(line[..line.find(" 25.00%")?].width()))
+                .collect();
+            assert_eq!(columns.len(), 4);
+            assert!(columns.iter().all(|&column| column == columns[0]));
+        }
+    }
+
+    #[test]
+    fn label_overflow() {
+        #[rustfmt::skip]
+        let data = [
+            Data { label: "/var/log/nginx/access.log".into(), value: 1.0, fill: '*', ..Default::default() },
+            Data { label: "authentication service backend".into(), value: 1.0, fill: '+', ..Default::default() },
+        ];
+        let draw = |overflow| {
+            let mut output = Vec::new();
+            Chart::new()
+                .radius(0)
+                .legend(true)
+                .legend_width(24)
+                .label_overflow(overflow)
+                .legend_position(LegendPosition::Below)
+                .draw_into(&mut output, &data)
+                .unwrap();
+            let output = String::from_utf8(output).unwrap();
+            output
+                .lines()
+                .skip(1)
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let lines = draw(LabelOverflow::Truncate);
+        assert_eq!(
+            lines,
+            ["* /var/log/n… 50.00% (1)", "+ authentica… 50.00% (1)"]
+        );
+        let lines = draw(LabelOverflow::TruncateMiddle);
+        assert_eq!(
+            lines,
+            ["* /var/…s.log 50.00% (1)", "+ authe…ckend 50.00% (1)"]
+        );
+
+        // wrapped labels continue in the column of the label
+        let lines = draw(LabelOverflow::Wrap);
+        assert_eq!(
+            lines,
+            [
+                "* /var/log/ng 50.00% (1)",
+                "  inx/access.",
+                "  log",
+                "+ authenticat 50.00% (1)",
+                "  ion service",
+                "  backend",
+            ]
+        );
+    }
+
+    #[test]
+    fn label_overflow_templates() {
+        #[rustfmt::skip]
+        let data = [
+            Data { label: "/var/log/nginx/access.log".into(), value: 1.0, fill: '*', ..Default::default() },
+            Data { label: "authentication service backend".into(), value: 1.0, fill: '+', ..Default::default() },
+        ];
+        let draw = |format, width, overflow| {
+            let mut output = Vec::new();
+            Chart::new()
+                .radius(0)
+                .legend(true)
+                .legend_width(width)
+                .legend_format(format)
+                .label_overflow(overflow)
+                .legend_position(LegendPosition::Below)
+                .draw_into(&mut output, &data)
+                .unwrap();
+            let output = String::from_utf8(output).unwrap();
+            output
+                .lines()
+                .skip(1)
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        // entries without the label aren't shortened
+        let lines = draw("{fill} {percent}% of {value}", 12, LabelOverflow::Wrap);
+        assert_eq!(lines, ["* 50.00% of 1", "+ 50.00% of 1"]);
+
+        // padded labels are shortened until the padded entry fits
+        let lines = draw("{fill} {label:>12} {percent}%", 24, LabelOverflow::Wrap);
+        assert_eq!(
+            lines,
+            [
+                "* /var/log/nginx/ 50.00%",
+                "  access.log",
+                "+ authentication 50.00%",
+                "  service backend",
+            ]
+        );
+        let lines = draw("{fill} {label:>16} {percent}%", 20, LabelOverflow::Truncate);
+        assert_eq!(
+            lines,
+            ["*                … 50.00%", "+                … 50.00%"]
+        );
+        let lines = draw(
+            "{fill} {label} {percent}%",
+            9,
+            LabelOverflow::TruncateMiddle,
+        );
+        assert_eq!(lines, ["* … 50.00%", "+ … 50.00%"]);
+    }
+
+    #[test]
+    fn fit_radius() {
+        let data = vec![Default::default(); 3];
+        let size = |chart: &Chart| {
+            let mut output = Vec::new();
+            chart.draw_into(&mut output, &data).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            let width = output.lines().map(|line| line.chars().count()).max();
+            (width.unwrap(), output.lines().count())
+        };
+
+        let mut chart = Chart::new();
+        chart.legend(true);
+        for &(columns, rows) in &[(80, 24), (120, 40), (40, 50), (30, 5)] {
+            let radius = chart.fit_radius(&data, columns, rows);
+            let (width, height) = size(chart.radius(radius));
+            assert!(width <= columns as usize && height <= rows as usize);
+
+            let (width, height) = size(chart.radius(radius + 1));
+            assert!(width > columns as usize || height > rows as usize);
+        }
+        assert_eq!(chart.fit_radius(&data, 10, 2), 0);
+    }
+
+    #[test]
+    fn size_and_dimensions() {
+        let data = vec![Default::default(); 3];
+        let mut chart = Chart::new();
+        chart.legend(true).total(true);
+        for &(columns, rows) in &[(41, 21), (60, 11), (20, 15), (7, 7), (1, 9), (1, 1)] {
+            // the pie has a center cell, so it is one column or row smaller than even sizes
+            let odd = |size: u16| (size as usize - 1) / 2 * 2 + 1;
+            let dimensions = chart.size(columns, rows).dimensions(&data);
+            assert_eq!(dimensions.pie_rows, odd(rows));
+            assert_eq!(dimensions.pie_columns, odd(columns));
+
+            let mut output = Vec::new();
+            chart.draw_into(&mut output, &data).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            let width = output.lines().map(|line| line.chars().count()).max();
+            assert_eq!(dimensions.columns, width.unwrap());
+            assert_eq!(dimensions.rows, output.lines().count());
+        }
+
+        // the box is applied when drawing, so the order of the settings doesn't matter
+        for render_mode in &[RenderMode::HalfBlock, RenderMode::Braille, RenderMode::Fill] {
+            let dimensions = chart
+                .size(41, 11)
+                .render_mode(*render_mode)
+                .dimensions(&data);
+            assert_eq!((dimensions.pie_columns, dimensions.pie_rows), (41, 11));
+        }
+        let dimensions = chart.size(41, 11).radius(2).dimensions(&data);
+        assert_eq!(dimensions.pie_rows, 5);
+    }
+
+    #[should_panic]
+    #[test]
+    fn legend_format_unknown_placeholder() {
+        Chart::new().legend_format("{fill} {name}");
+    }
+
     #[should_panic]
     #[test]
     fn zero_total() {
@@ -0,0 +1,166 @@
+use crate::Data;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// The prefixes values are scaled with, see [`Chart::unit_prefix`](struct.Chart.html#method.unit_prefix).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitPrefix {
+    /// Values are written as they are.
+    None,
+    /// Values are scaled by powers of 1000 and get a `k`, `M`, `G`, `T` or `P` suffix.
+    Si,
+    /// Values are scaled by powers of 1024 and get a `Ki`, `Mi`, `Gi`, `Ti` or `Pi` suffix.
+    Binary,
+}
+
+/// How values and the total are written in the legend.
+#[derive(Debug, Clone)]
+pub(crate) struct NumberFormat {
+    pub precision: Option<usize>,
+    pub thousands_separator: Option<char>,
+    pub prefix: UnitPrefix,
+    pub unit: String,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            precision: None,
+            thousands_separator: None,
+            prefix: UnitPrefix::None,
+            unit: String::new(),
+        }
+    }
+}
+
+impl NumberFormat {
+    pub fn format(&self, value: f32) -> String {
+        let (base, prefixes): (f32, &[&str]) = match self.prefix {
+            UnitPrefix::None => (1.0, &[""]),
+            UnitPrefix::Si => (1000.0, &["", "k", "M", "G", "T", "P"]),
+            UnitPrefix::Binary => (1024.0, &["", "Ki", "Mi", "Gi", "Ti", "Pi"]),
+        };
+        let (mut value, mut prefix) = (value, 0);
+        while value.abs() >= base && prefix + 1 < prefixes.len() {
+            value /= base;
+            prefix += 1;
+        }
+
+        let number = match self.precision {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => value.to_string(),
+        };
+        let number = match self.thousands_separator {
+            Some(separator) => group_thousands(&number, separator),
+            None => number,
+        };
+        format!("{}{}{}", number, prefixes[prefix], self.unit)
+    }
+}
+
+/// The percentages of the items at one precision and their running sums.
+type Rounded = (Vec<f32>, Vec<f32>);
+
+/// The percentages of the data items as they are displayed.
+#[derive(Debug, Clone)]
+pub(crate) struct Percentages<'a> {
+    data: &'a [Data],
+    total: f32,
+    exact: bool,
+    /// The percentages and their running sums for each precision which was asked for.
+    cache: RefCell<HashMap<usize, Rounded>>,
+}
+
+impl<'a> Percentages<'a> {
+    /// If `exact` is set, the percentages are rounded so that they add up to exactly 100.
+    pub fn new(data: &'a [Data], total: f32, exact: bool) -> Self {
+        Percentages {
+            data,
+            total,
+            exact,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The percentage of the item at `idx`, as it is displayed with `precision` decimal places.
+    pub fn get(&self, idx: usize, precision: usize) -> f32 {
+        self.with_precision(precision, |percentages, _| percentages[idx])
+    }
+
+    /// The sum of the percentages of the items up to and including the one at `idx`.
+    pub fn cumulative(&self, idx: usize, precision: usize) -> f32 {
+        self.with_precision(precision, |_, cumulative| cumulative[idx])
+    }
+
+    /// Calls `f` with the percentages at `precision` and their running sums, which are only computed once.
+    fn with_precision<T>(&self, precision: usize, f: impl FnOnce(&[f32], &[f32]) -> T) -> T {
+        let mut cache = self.cache.borrow_mut();
+        let (percentages, cumulative) = cache.entry(precision).or_insert_with(|| {
+            let percentages: Vec<f32> = match self.exact {
+                true => self.rounded(precision),
+                false => self
+                    .data
+                    .iter()
+                    .map(|item| item.value / self.total * 100.0)
+                    .collect(),
+            };
+            let cumulative = percentages
+                .iter()
+                .scan(0.0, |sum, percent| {
+                    *sum += percent;
+                    Some(*sum)
+                })
+                .collect();
+            (percentages, cumulative)
+        });
+        f(percentages, cumulative)
+    }
+
+    /// Rounds the percentages with the largest remainder method: every percentage is rounded down,
+    /// then the remaining units are given to the items with the largest fractional parts.
+    fn rounded(&self, precision: usize) -> Vec<f32> {
+        let scale = 10f64.powi(precision as i32);
+        let exact: Vec<f64> = self
+            .data
+            .iter()
+            .map(|item| item.value as f64 / self.total as f64 * 100.0 * scale)
+            .collect();
+        let mut units: Vec<f64> = exact.iter().map(|percent| percent.floor()).collect();
+
+        let remaining = (100.0 * scale - units.iter().sum::<f64>()).round().max(0.0) as usize;
+        let mut by_remainder: Vec<usize> = (0..exact.len()).collect();
+        by_remainder.sort_by(|a, b| {
+            let remainder = |i: usize| exact[i] - units[i];
+            remainder(*b)
+                .partial_cmp(&remainder(*a))
+                .unwrap()
+                .then(a.cmp(b))
+        });
+        for &idx in by_remainder.iter().take(remaining) {
+            units[idx] += 1.0;
+        }
+
+        units.iter().map(|units| (units / scale) as f32).collect()
+    }
+}
+
+/// Inserts `separator` between every three digits of the integer part of `number`.
+fn group_thousands(number: &str, separator: char) -> String {
+    let (sign, number) = match number.strip_prefix('-') {
+        Some(number) => ("-", number),
+        None => ("", number),
+    };
+    let (integer, fraction) = match number.find('.') {
+        Some(i) => number.split_at(i),
+        None => (number, ""),
+    };
+
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    format!("{}{}{}", sign, grouped, fraction)
+}
@@ -0,0 +1,29 @@
+use std::env;
+
+/// Returns the number of columns and rows of the terminal stdout is connected to.
+/// If stdout isn't a terminal, the `COLUMNS` and `LINES` environment variables are used instead.
+pub(crate) fn size() -> Option<(u16, u16)> {
+    ioctl_size().or_else(env_size)
+}
+
+#[cfg(unix)]
+fn ioctl_size() -> Option<(u16, u16)> {
+    // SAFETY: `winsize` only consists of integers, for which zero is a valid value
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    // SAFETY: `TIOCGWINSZ` takes a pointer to a `winsize` and only writes into it
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    match result == 0 && size.ws_col > 0 && size.ws_row > 0 {
+        true => Some((size.ws_col, size.ws_row)),
+        false => None,
+    }
+}
+
+#[cfg(not(unix))]
+fn ioctl_size() -> Option<(u16, u16)> {
+    None
+}
+
+fn env_size() -> Option<(u16, u16)> {
+    let var = |name| env::var(name).ok()?.trim().parse().ok();
+    Some((var("COLUMNS")?, var("LINES")?))
+}
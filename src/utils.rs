@@ -1,4 +1,5 @@
-use crate::Data;
+use crate::{Color, Data};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 pub fn data_angles(total: f32, data: &[Data]) -> Vec<f32> {
     let mut angle = 0.0;
@@ -12,23 +13,219 @@ pub fn data_angles(total: f32, data: &[Data]) -> Vec<f32> {
         .collect()
 }
 
-pub fn calculate_width(radius: i32, y: i32, aspect_ratio: i32) -> i32 {
-    let val = radius.pow(2) - y.pow(2);
-    let width = ((val * aspect_ratio) as f32).sqrt().round() as i32;
+pub fn calculate_width(radius: i32, y: i32, aspect_ratio: f32) -> i32 {
+    // the ellipse only touches the center of the first and last line of the circle, which would leave
+    // an ugly single dot on top and bottom. Those lines are measured a quarter row further inside instead
+    let y = match y.abs() == radius && radius > 0 {
+        true => y.abs() as f32 - 0.25,
+        false => y as f32,
+    };
+    let val = (radius as f32).powi(2) - y.powi(2);
 
-    // the next code applies to the first and last line of the circle.
-    // without it the circles have an ugly single dot on top and bottom.
-    // I just experimented until it looked alright for all combinations of radius and aspect_ratio
-    match (width, aspect_ratio) {
-        (0, 1) => width,
-        (0, 2) => radius / (aspect_ratio * 2),
-        (0, _) => radius / aspect_ratio,
-        _ => width,
+    (val * aspect_ratio).max(0.0).sqrt().round() as i32
+}
+
+pub fn calculate_center_x(radius: i32, aspect_ratio: f32) -> i32 {
+    (radius as f32 * aspect_ratio.sqrt()).round() as i32
+}
+
+/// Centers `text` in a field of `width` columns, truncating it with an ellipsis if it is too long.
+pub fn center_text(text: &str, width: usize) -> String {
+    let text = truncate_end(text, width);
+    let padding = width - text.width();
+
+    format!(
+        "{}{}{}",
+        " ".repeat(padding / 2),
+        text,
+        " ".repeat(padding - padding / 2)
+    )
+}
+
+/// Returns the longest start of `text` which is at most `width` columns wide.
+pub fn truncate(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+/// Shortens `text` to at most `width` columns by replacing its end with an ellipsis.
+pub fn truncate_end(text: &str, width: usize) -> String {
+    match text.width() > width {
+        true if width == 0 => String::new(),
+        true => truncate(text, width - 1) + "…",
+        false => text.to_string(),
+    }
+}
+
+/// Shortens `text` to at most `width` columns by replacing its middle with an ellipsis,
+/// which keeps the end of e.g. file paths readable.
+pub fn truncate_middle(text: &str, width: usize) -> String {
+    if text.width() <= width || width == 0 {
+        return truncate_end(text, width);
+    }
+    let end_width = (width - 1) / 2;
+    let reversed: String = text.chars().rev().collect();
+    let end: String = truncate(&reversed, end_width).chars().rev().collect();
+    truncate(text, width - 1 - end.width()) + "…" + &end
+}
+
+/// Splits `text` into lines of at most `width` columns, breaking it between words if possible.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![String::new()];
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        loop {
+            let line = lines.last_mut().expect("there is always a line");
+            let space = !line.is_empty() as usize;
+            if line.width() + space + word.width() <= width {
+                if space == 1 {
+                    line.push(' ');
+                }
+                line.push_str(&word);
+                break;
+            }
+            if !line.is_empty() {
+                lines.push(String::new());
+                continue;
+            }
+            // words which are longer than a line are split
+            let start = truncate(&word, width);
+            let start = match start.is_empty() {
+                true => word.chars().take(1).collect(),
+                false => start,
+            };
+            word = word[start.len()..].to_string();
+            lines
+                .last_mut()
+                .expect("there is always a line")
+                .push_str(&start);
+            if word.is_empty() {
+                break;
+            }
+            lines.push(String::new());
+        }
     }
+    lines
 }
 
-pub fn calculate_center_x(radius: i32, aspect_ratio: i32) -> i32 {
-    let aspect_ratio_sqrt = (aspect_ratio as f32).sqrt();
+/// Returns the amount of columns `text` takes up in the terminal, ignoring ANSI escape codes.
+/// East Asian wide chars take up two columns and combining marks none.
+pub fn text_width(text: &str) -> usize {
+    let mut escape = false;
+    let text: String = text
+        .chars()
+        .filter(|c| match (escape, *c) {
+            (false, '\x1b') => {
+                escape = true;
+                false
+            }
+            (true, c) => {
+                escape = !c.is_ascii_alphabetic();
+                false
+            }
+            (false, _) => true,
+        })
+        .collect();
+    text.width()
+}
+
+/// Returns the index which occurs most often, preferring the smaller index on ties.
+pub fn most_common(indices: impl Iterator<Item = usize>) -> Option<usize> {
+    let mut counts: Vec<(usize, usize)> = Vec::new();
+    for idx in indices {
+        match counts.iter_mut().find(|(i, _)| *i == idx) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((idx, 1)),
+        }
+    }
+    counts
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+        .map(|(idx, _)| idx)
+}
+
+/// Returns the braille pattern with the given dots set. `dots` contains the 2x4 dots row by row.
+pub fn braille(dots: &[bool]) -> char {
+    // the bits of the first three rows go top to bottom, column by column, the last row was added later
+    let bits = dots
+        .iter()
+        .enumerate()
+        .filter(|(_, set)| **set)
+        .map(|(i, _)| match (i % 2, i / 2) {
+            (col, 3) => 6 + col,
+            (col, row) => col * 3 + row,
+        })
+        .fold(0, |bits, bit| bits | 1 << bit);
+
+    std::char::from_u32(0x2800 + bits).expect("braille patterns are valid chars")
+}
 
-    (radius as f32 * aspect_ratio_sqrt).round() as i32
+/// Returns the block char with the given quadrants (top left, top right, bottom left, bottom right) set.
+pub fn quadrant_block(quadrants: [bool; 4]) -> char {
+    const BLOCKS: [char; 16] = [
+        ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+    ];
+    let idx = quadrants
+        .iter()
+        .enumerate()
+        .filter(|(_, set)| **set)
+        .fold(0, |idx, (bit, _)| idx | 1 << bit);
+    BLOCKS[idx]
+}
+
+/// Returns the line char closest to the direction (`x`, `y`), where `y` points down and is in units of a cell's width.
+pub fn line_symbol(x: f32, y: f32) -> char {
+    match (-y).atan2(x).to_degrees().rem_euclid(180.0) {
+        angle if angle < 22.5 => '─',
+        angle if angle < 67.5 => '╱',
+        angle if angle < 112.5 => '│',
+        angle if angle < 157.5 => '╲',
+        _ => '─',
+    }
+}
+
+/// Returns black or white, whichever is more readable on top of `color`.
+pub fn contrast(color: Color) -> Color {
+    const BASIC: [(u8, u8, u8); 8] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+    ];
+    let cube = |v: u8| if v == 0 { 0 } else { 55 + 40 * v };
+
+    let (r, g, b) = match color {
+        Color::Black => BASIC[0],
+        Color::Red => BASIC[1],
+        Color::Green => BASIC[2],
+        Color::Yellow => BASIC[3],
+        Color::Blue => BASIC[4],
+        Color::Purple => BASIC[5],
+        Color::Cyan => BASIC[6],
+        Color::White => BASIC[7],
+        Color::Fixed(n) if n < 16 => BASIC[n as usize % 8],
+        Color::Fixed(n) if n < 232 => (
+            cube((n - 16) / 36),
+            cube((n - 16) / 6 % 6),
+            cube((n - 16) % 6),
+        ),
+        Color::Fixed(n) => (8 + 10 * (n - 232), 8 + 10 * (n - 232), 8 + 10 * (n - 232)),
+        Color::RGB(r, g, b) => (r, g, b),
+    };
+
+    let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    match luminance > 128.0 {
+        true => Color::Black,
+        false => Color::White,
+    }
 }